@@ -0,0 +1,442 @@
+// Sprite-sheet animation: clips of frames cut from a sheet, played back by
+// delta time.
+use crate::render::{Console, Rect, Sprite};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// What a clip does once it reaches its last frame.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PlayMode {
+    /// Starts again from the first frame.
+    Loop,
+    /// Plays backwards to the first frame, then forwards again, and so on.
+    PingPong,
+    /// Stops on the last frame.
+    Once,
+}
+
+/// Part of a sprite sheet, shown for `duration` seconds.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Frame {
+    pub source: Rect,
+    pub duration: f64,
+}
+
+/// A sequence of frames from a sprite sheet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    frames: Vec<Frame>,
+    mode: PlayMode,
+}
+
+impl Clip {
+    pub fn new(mode: PlayMode) -> Clip {
+        Clip {
+            frames: Vec::new(),
+            mode,
+        }
+    }
+
+    /// Adds a frame to the end of the clip; panics if `duration` isn't more
+    /// than zero.
+    pub fn add_frame(&mut self, source: Rect, duration: f64) {
+        assert!(duration > 0.0, "Frame durations must be more than zero");
+
+        self.frames.push(Frame { source, duration });
+    }
+
+    pub fn get_frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn get_mode(&self) -> PlayMode {
+        self.mode
+    }
+
+    /// How long one pass through the clip takes, in seconds.
+    pub fn get_duration(&self) -> f64 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+}
+
+/// A sprite sheet and the clips cut from it, by name.
+#[derive(Debug, Clone)]
+pub struct AnimationSet {
+    sheet: Sprite,
+    clips: HashMap<String, Clip>,
+}
+
+impl AnimationSet {
+    /// Creates a set with no clips.
+    pub fn new(sheet: Sprite) -> AnimationSet {
+        AnimationSet {
+            sheet,
+            clips: HashMap::new(),
+        }
+    }
+
+    /// Loads clips from a text file, and the sheet from the .spr file next to
+    /// it with the same name; so `player.anim` is drawn from `player.spr`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<AnimationSet, Box<dyn Error>> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let sheet = Sprite::load(path.with_extension("spr"))?;
+
+        Ok(AnimationSet::parse(&text, sheet)?)
+    }
+
+    /// Reads clips from text, each a `[name]` line with its play mode
+    /// (`loop`, `ping_pong` or `once`) followed by its frames, one per line:
+    ///
+    /// ```text
+    /// ; x y width height seconds
+    /// [walk] loop
+    /// 0 0 4 3 0.1
+    /// 4 0 4 3 0.1
+    ///
+    /// [die] once
+    /// 8 0 4 3 0.2
+    /// ```
+    ///
+    /// Blank lines and lines starting with ';' are skipped.
+    pub fn parse(text: &str, sheet: Sprite) -> Result<AnimationSet, AnimationError> {
+        let mut set = AnimationSet::new(sheet);
+        let mut current: Option<(String, Clip)> = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: String| AnimationError {
+                line: index + 1,
+                message,
+            };
+
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let (name, mode) = header
+                    .split_once(']')
+                    .ok_or_else(|| error("the clip name is missing its ']'".into()))?;
+
+                let mode = match mode.trim() {
+                    "loop" => PlayMode::Loop,
+                    "ping_pong" => PlayMode::PingPong,
+                    "once" => PlayMode::Once,
+                    "" => return Err(error(format!("'{}' has no play mode", name))),
+                    mode => return Err(error(format!("unknown play mode '{}'", mode))),
+                };
+
+                set.finish_clip(current.take());
+
+                if set.clips.contains_key(name) {
+                    return Err(error(format!("there is already a clip called '{}'", name)));
+                }
+
+                current = Some((name.to_string(), Clip::new(mode)));
+                continue;
+            }
+
+            let (_, clip) = current
+                .as_mut()
+                .ok_or_else(|| error("frames must come after a [name] line".into()))?;
+
+            let numbers: Vec<&str> = line.split_whitespace().collect();
+
+            let [x, y, width, height, duration] = numbers[..] else {
+                return Err(error(
+                    "expected a frame's x, y, width, height and seconds".into(),
+                ));
+            };
+
+            let number = |word: &str| {
+                word.parse::<i32>()
+                    .map_err(|_| error(format!("'{}' is not a whole number", word)))
+            };
+            let source = Rect::new(number(x)?, number(y)?, number(width)?, number(height)?);
+
+            match duration.parse::<f64>() {
+                Ok(seconds) if seconds > 0.0 => clip.add_frame(source, seconds),
+                _ => return Err(error(format!("'{}' is not a duration", duration))),
+            }
+        }
+
+        set.finish_clip(current);
+
+        Ok(set)
+    }
+
+    /// Adds a clip, replacing any with the same name.
+    pub fn insert(&mut self, name: &str, clip: Clip) {
+        self.clips.insert(name.to_string(), clip);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Clip> {
+        self.clips.get(name)
+    }
+
+    pub fn get_sheet(&self) -> &Sprite {
+        &self.sheet
+    }
+
+    fn finish_clip(&mut self, clip: Option<(String, Clip)>) {
+        if let Some((name, clip)) = clip {
+            self.clips.insert(name, clip);
+        }
+    }
+}
+
+/// Why an animation file couldn't be read; `line` counts from 1.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AnimationError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AnimationError {}
+
+/// Something that happened to the playing clip during `Animator::update`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AnimationEvent {
+    /// A looping or ping-pong clip went back to its first frame.
+    Looped,
+    /// A one-shot clip reached the end of its last frame.
+    Finished,
+}
+
+/// Plays clips from an AnimationSet; keep one per animated thing.
+#[derive(Debug, Clone, Default)]
+pub struct Animator {
+    clip: Option<String>,
+    frame: usize,
+    time: f64,
+    backwards: bool,
+    finished: bool,
+}
+
+impl Animator {
+    pub fn new() -> Animator {
+        Animator::default()
+    }
+
+    /// Switches to a clip from its first frame; playing the clip that is
+    /// already playing carries on without restarting it.
+    pub fn play(&mut self, name: &str) {
+        if self.clip.as_deref() != Some(name) {
+            self.clip = Some(name.to_string());
+            self.restart();
+        }
+    }
+
+    /// Goes back to the start of the current clip.
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.time = 0.0;
+        self.backwards = false;
+        self.finished = false;
+    }
+
+    pub fn get_clip(&self) -> Option<&str> {
+        self.clip.as_deref()
+    }
+
+    /// The index of the frame being shown.
+    pub fn get_frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Whether a one-shot clip has played to the end.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Moves the current clip on by `delta` seconds, returning what happened
+    /// along the way; when a long delta loops a clip more than once, Looped is
+    /// still only returned once. Panics if the clip isn't in the set.
+    pub fn update(&mut self, set: &AnimationSet, delta: f64) -> Option<AnimationEvent> {
+        let clip = match &self.clip {
+            Some(name) => set
+                .get(name)
+                .unwrap_or_else(|| panic!("There is no clip called '{}'", name)),
+            None => return None,
+        };
+        let frames = clip.get_frames();
+
+        if self.finished || frames.is_empty() {
+            return None;
+        }
+
+        let mut event = None;
+        self.time += delta;
+
+        while self.time >= frames[self.frame].duration {
+            self.time -= frames[self.frame].duration;
+
+            let last = frames.len() - 1;
+
+            match clip.get_mode() {
+                PlayMode::Once if self.frame == last => {
+                    self.time = 0.0;
+                    self.finished = true;
+
+                    return Some(AnimationEvent::Finished);
+                }
+                PlayMode::Loop if self.frame == last => {
+                    self.frame = 0;
+                    event = Some(AnimationEvent::Looped);
+                }
+                PlayMode::Once | PlayMode::Loop => self.frame += 1,
+                PlayMode::PingPong => {
+                    if last == 0 {
+                        event = Some(AnimationEvent::Looped);
+                    } else if self.backwards {
+                        self.frame -= 1;
+
+                        if self.frame == 0 {
+                            self.backwards = false;
+                            event = Some(AnimationEvent::Looped);
+                        }
+                    } else {
+                        self.frame += 1;
+                        self.backwards = self.frame == last;
+                    }
+                }
+            }
+        }
+
+        event
+    }
+
+    /// The part of the sheet to show for the current frame, if a clip with any
+    /// frames is playing.
+    pub fn get_source(&self, set: &AnimationSet) -> Option<Rect> {
+        let clip = set.get(self.clip.as_deref()?)?;
+
+        clip.get_frames().get(self.frame).map(|frame| frame.source)
+    }
+
+    /// Draws the current frame with its top left corner at (x, y).
+    pub fn draw(&self, console: &mut Console, set: &AnimationSet, x: i32, y: i32) {
+        if let Some(source) = self.get_source(set) {
+            console.draw_partial_sprite(x, y, set.get_sheet(), source);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIPS: &str = "; x y width height seconds
+[walk] loop
+0 0 2 2 0.25
+2 0 2 2 0.25
+4 0 2 2 0.5
+
+[bob] ping_pong
+0 2 2 2 0.25
+2 2 2 2 0.25
+4 2 2 2 0.25
+
+[die] once
+0 4 2 2 0.5
+";
+
+    fn frames(animator: &mut Animator, set: &AnimationSet, steps: usize) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                animator.update(set, 0.25);
+                animator.get_frame()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let set = AnimationSet::parse(CLIPS, Sprite::new(6, 6)).unwrap();
+        let walk = set.get("walk").unwrap();
+
+        assert_eq!(walk.get_mode(), PlayMode::Loop);
+        assert_eq!(walk.get_frames().len(), 3);
+        assert_eq!(walk.get_frames()[1].source, Rect::new(2, 0, 2, 2));
+        assert!(walk.get_duration() == 1.0);
+        assert_eq!(set.get("die").unwrap().get_mode(), PlayMode::Once);
+
+        let error = |text: &str| {
+            AnimationSet::parse(text, Sprite::new(1, 1))
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error("0 0 1 1 0.1"),
+            "line 1: frames must come after a [name] line"
+        );
+        assert_eq!(error("[walk] skip"), "line 1: unknown play mode 'skip'");
+        assert_eq!(
+            error("[walk] loop\n0 0 1 0.1"),
+            "line 2: expected a frame's x, y, width, height and seconds"
+        );
+        assert_eq!(
+            error("[walk] loop\n0 0 1 1 0"),
+            "line 2: '0' is not a duration"
+        );
+        assert_eq!(
+            error("[a] loop\n[a] once"),
+            "line 2: there is already a clip called 'a'"
+        );
+    }
+
+    #[test]
+    fn test_play_modes() {
+        let set = AnimationSet::parse(CLIPS, Sprite::new(6, 6)).unwrap();
+        let mut animator = Animator::new();
+
+        animator.play("walk");
+        assert_eq!(frames(&mut animator, &set, 5), [1, 2, 2, 0, 1]);
+
+        // Playing the same clip again doesn't restart it
+        animator.play("walk");
+        assert_eq!(animator.get_frame(), 1);
+        assert_eq!(animator.get_source(&set), Some(Rect::new(2, 0, 2, 2)));
+
+        animator.play("bob");
+        assert_eq!(frames(&mut animator, &set, 6), [1, 2, 1, 0, 1, 2]);
+
+        animator.play("die");
+        assert_eq!(animator.update(&set, 0.3), None);
+        assert_eq!(animator.update(&set, 0.3), Some(AnimationEvent::Finished));
+        assert!(animator.is_finished());
+        assert_eq!(animator.update(&set, 1.0), None);
+        assert_eq!(animator.get_frame(), 0);
+    }
+
+    #[test]
+    fn test_events() {
+        let set = AnimationSet::parse(CLIPS, Sprite::new(6, 6)).unwrap();
+        let mut animator = Animator::new();
+
+        assert_eq!(animator.update(&set, 1.0), None);
+
+        animator.play("walk");
+        assert_eq!(animator.update(&set, 0.75), None);
+        assert_eq!(animator.update(&set, 0.25), Some(AnimationEvent::Looped));
+
+        // Several loops in one update are reported once
+        assert_eq!(animator.update(&set, 2.0), Some(AnimationEvent::Looped));
+        assert_eq!(animator.get_frame(), 0);
+
+        animator.play("bob");
+        assert_eq!(animator.update(&set, 0.6), None);
+        assert_eq!(animator.update(&set, 0.5), Some(AnimationEvent::Looped));
+    }
+}
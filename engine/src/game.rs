@@ -0,0 +1,273 @@
+// Engine-owned game loop, in the style of olcConsoleGameEngine's OnUserCreate/OnUserUpdate.
+use crate::input::{Key, Keyboard};
+use crate::render::{CastRecorder, Console, HeadlessBackend, ScreenshotFormat};
+use crate::time::{Clock, FramePacer};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Whether the game loop should keep going after this frame.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ControlFlow {
+    Continue,
+    Exit,
+}
+
+/// A game driven by `run`; only `on_update` has to be implemented.
+pub trait Game {
+    /// Called once, after the console and keyboard are set up.
+    fn on_create(&mut self, _context: &mut Context) {}
+
+    /// Called every frame with the seconds since the previous frame; draw into
+    /// `context.console` and the engine presents it once this returns.
+    fn on_update(&mut self, context: &mut Context, delta: f64) -> ControlFlow;
+
+    /// Called once after `on_update` returns `ControlFlow::Exit`, before the
+    /// console and keyboard hand the terminal back.
+    fn on_destroy(&mut self, _context: &mut Context) {}
+}
+
+// Lets `run` borrow a game, so its state can still be read once it exits
+impl<G: Game> Game for &mut G {
+    fn on_create(&mut self, context: &mut Context) {
+        (**self).on_create(context)
+    }
+
+    fn on_update(&mut self, context: &mut Context, delta: f64) -> ControlFlow {
+        (**self).on_update(context, delta)
+    }
+
+    fn on_destroy(&mut self, context: &mut Context) {
+        (**self).on_destroy(context)
+    }
+}
+
+/// Everything `run` owns that a game needs each frame.
+pub struct Context {
+    pub console: Console,
+    pub keyboard: Keyboard,
+    pub clock: Clock,
+}
+
+/// Settings for `run`; start from `Config::new` and override what you need.
+pub struct Config {
+    pub width: u16,
+    pub height: u16,
+    /// Ignored on Unix terminals, where the font can't be changed.
+    pub font_width: u16,
+    pub font_height: u16,
+    pub title: String,
+    /// The keys the Keyboard tracks.
+    pub keys: Vec<Key>,
+    pub target_fps: f64,
+    /// Draw into a HeadlessBackend instead of the terminal.
+    pub headless: bool,
+    /// See `Console::set_dithering`.
+    pub dithering: bool,
+    /// Pressing this key saves the frame as screenshot-N.ans, .html and .ppm
+    /// in the working directory, with the first N that isn't taken; off when None.
+    pub screenshot_key: Option<Key>,
+    /// Records the session to this asciinema .cast file; see
+    /// `Console::start_recording`.
+    pub recording: Option<PathBuf>,
+}
+
+impl Config {
+    pub fn new(width: u16, height: u16, title: &str) -> Config {
+        Config {
+            width,
+            height,
+            font_width: 16,
+            font_height: 16,
+            title: title.to_string(),
+            keys: vec![Key::ESCAPE],
+            target_fps: 60.0,
+            headless: false,
+            dithering: false,
+            screenshot_key: None,
+            recording: None,
+        }
+    }
+}
+
+/// Settings chosen by whoever launches a game rather than by the game itself,
+/// such as the runner's command line flags.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// See `Config::headless`.
+    pub headless: bool,
+    /// See `Config::recording`.
+    pub recording: Option<PathBuf>,
+}
+
+impl RunOptions {
+    /// Applies the options over a game's own config.
+    pub fn apply(self, config: Config) -> Config {
+        Config {
+            headless: self.headless || config.headless,
+            recording: self.recording.or(config.recording),
+            ..config
+        }
+    }
+}
+
+/// Sets up the console, keyboard and clock, then runs `game` until it exits.
+pub fn run<G: Game>(mut game: G, config: Config) -> Result<(), Box<dyn Error>> {
+    let mut console = if config.headless {
+        Console::with_backend(
+            config.width,
+            config.height,
+            Box::new(HeadlessBackend::new()),
+        )
+    } else {
+        Console::create(
+            config.width,
+            config.height,
+            config.font_width,
+            config.font_height,
+            &config.title,
+        )?
+    };
+
+    console.set_dithering(config.dithering);
+
+    if let Some(path) = &config.recording {
+        let recorder = CastRecorder::create(path, config.width, config.height, &config.title)?;
+
+        console.start_recording(recorder);
+    }
+
+    let mut keys = config.keys;
+    let screenshot_key = config.screenshot_key;
+
+    if let Some(key) = screenshot_key {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    let mut context = Context {
+        console,
+        keyboard: Keyboard::create(keys),
+        clock: Clock::new(),
+    };
+
+    let mut pacer = FramePacer::new(config.target_fps);
+
+    game.on_create(&mut context);
+
+    loop {
+        let delta = context.clock.tick();
+
+        context.keyboard.update_key_states();
+
+        if game.on_update(&mut context, delta) == ControlFlow::Exit {
+            break;
+        }
+
+        let presented = context
+            .console
+            .update_screen()
+            .and_then(|()| match screenshot_key {
+                Some(key) if context.keyboard.get_key_state(key).is_pressed() => {
+                    save_screenshots(&context.console)
+                }
+                _ => Ok(()),
+            });
+
+        if let Err(error) = presented {
+            game.on_destroy(&mut context);
+
+            return Err(error);
+        }
+
+        pacer.wait();
+    }
+
+    game.on_destroy(&mut context);
+
+    Ok(())
+}
+
+// Saves the frame in every screenshot format under the first free number
+fn save_screenshots(console: &Console) -> Result<(), Box<dyn Error>> {
+    let path = |number: usize, format: ScreenshotFormat| {
+        format!("screenshot-{}.{}", number, format.get_extension())
+    };
+
+    let number = (1..)
+        .find(|&number| {
+            ScreenshotFormat::ALL
+                .iter()
+                .all(|&format| !Path::new(&path(number, format)).exists())
+        })
+        .unwrap();
+
+    for format in ScreenshotFormat::ALL {
+        console.save_screenshot(path(number, format))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{colour, Pixel, PIXEL};
+
+    #[derive(Default)]
+    struct CountingGame {
+        created: bool,
+        updates: usize,
+        destroyed: bool,
+    }
+
+    impl Game for CountingGame {
+        fn on_create(&mut self, _context: &mut Context) {
+            self.created = true;
+        }
+
+        fn on_update(&mut self, context: &mut Context, delta: f64) -> ControlFlow {
+            assert!(self.created);
+            assert!(delta > 0.0);
+
+            context.console.draw_pixel(
+                self.updates as i32,
+                0,
+                &Pixel {
+                    char_value: PIXEL,
+                    attributes: colour::FG_WHITE,
+                    colours: None,
+                },
+            );
+
+            self.updates += 1;
+
+            if self.updates == 3 {
+                ControlFlow::Exit
+            } else {
+                ControlFlow::Continue
+            }
+        }
+
+        fn on_destroy(&mut self, context: &mut Context) {
+            assert_eq!(context.console.get_pixel(2, 0).char_value, PIXEL);
+            self.destroyed = true;
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let mut game = CountingGame::default();
+
+        let config = Config {
+            target_fps: 1000.0,
+            headless: true,
+            ..Config::new(10, 2, "TEST")
+        };
+
+        run(&mut game, config).unwrap();
+
+        assert_eq!(game.updates, 3);
+        assert!(game.destroyed);
+    }
+}
@@ -0,0 +1,304 @@
+// Unix input backend; reads stdin in raw, non-blocking mode and decodes the
+// bytes and escape sequences the terminal sends for each key press.
+use super::Key;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+// Terminals only report presses (plus auto-repeat), never releases. A key that is
+// auto-repeating stays down across gaps shorter than this.
+const REPEAT_TIMEOUT: Duration = Duration::from_millis(100);
+
+struct SeenKey {
+    last_seen: Instant,
+    repeating: bool,
+}
+
+pub struct RawInput {
+    // None when stdin is not a terminal, e.g. under `cargo test`
+    original_termios: Option<libc::termios>,
+    seen: HashMap<Key, SeenKey>,
+}
+
+impl RawInput {
+    pub fn create() -> RawInput {
+        RawInput {
+            original_termios: enable_raw_mode(),
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Reads every pending byte from stdin and returns the keys that are down.
+    pub fn poll(&mut self) -> HashSet<Key> {
+        let now = Instant::now();
+        let mut keys_down = HashSet::new();
+
+        if self.original_termios.is_some() {
+            for key in decode(&read_stdin()) {
+                keys_down.insert(key);
+
+                let repeating = match self.seen.get(&key) {
+                    Some(seen) => now.duration_since(seen.last_seen) < REPEAT_TIMEOUT,
+                    None => false,
+                };
+
+                self.seen.insert(
+                    key,
+                    SeenKey {
+                        last_seen: now,
+                        repeating,
+                    },
+                );
+            }
+        }
+
+        self.seen
+            .retain(|_, seen| now.duration_since(seen.last_seen) < REPEAT_TIMEOUT);
+
+        for (key, seen) in self.seen.iter() {
+            if seen.repeating {
+                keys_down.insert(*key);
+            }
+        }
+
+        keys_down
+    }
+}
+
+impl Drop for RawInput {
+    fn drop(&mut self) {
+        if let Some(original) = self.original_termios {
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &original);
+            }
+        }
+    }
+}
+
+fn enable_raw_mode() -> Option<libc::termios> {
+    unsafe {
+        if libc::isatty(libc::STDIN_FILENO) == 0 {
+            return None;
+        }
+
+        let mut termios: libc::termios = std::mem::zeroed();
+
+        if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+            return None;
+        }
+
+        let original = termios;
+
+        // No line buffering, echo or signal keys; Ctrl+C arrives as CONTROL + CHAR_C
+        termios.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+        termios.c_iflag &= !(libc::IXON | libc::ICRNL | libc::BRKINT | libc::ISTRIP);
+
+        // read() returns straight away, even when nothing is pending
+        termios.c_cc[libc::VMIN] = 0;
+        termios.c_cc[libc::VTIME] = 0;
+
+        if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) != 0 {
+            return None;
+        }
+
+        Some(original)
+    }
+}
+
+fn read_stdin() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut buffer = [0u8; 64];
+
+    loop {
+        let count = unsafe {
+            libc::read(
+                libc::STDIN_FILENO,
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+            )
+        };
+
+        if count <= 0 {
+            break;
+        }
+
+        bytes.extend_from_slice(&buffer[..count as usize]);
+    }
+
+    bytes
+}
+
+/// Decodes raw terminal input into keys; modifiers are reported as SHIFT, CONTROL
+/// and MENU alongside the key they were held with.
+pub fn decode(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            0x1b => match bytes.get(i + 1) {
+                Some(b'[') => i += decode_csi(&bytes[i + 2..], &mut keys) + 2,
+                Some(b'O') if i + 2 < bytes.len() => {
+                    decode_final(bytes[i + 2], 1, &mut keys);
+                    i += 3;
+                }
+                Some(&byte) if byte != 0x1b => {
+                    // ESC followed by a key is how terminals send ALT + key
+                    keys.push(Key::MENU);
+                    decode_byte(byte, &mut keys);
+                    i += 2;
+                }
+                _ => {
+                    keys.push(Key::ESCAPE);
+                    i += 1;
+                }
+            },
+            byte => {
+                decode_byte(byte, &mut keys);
+                i += 1;
+            }
+        }
+    }
+
+    keys
+}
+
+fn decode_byte(byte: u8, keys: &mut Vec<Key>) {
+    match byte {
+        b'\r' | b'\n' => keys.push(Key::RETURN),
+        b'\t' => keys.push(Key::TAB),
+        0x08 | 0x7f => keys.push(Key::BACK),
+        0x00 => keys.extend_from_slice(&[Key::CONTROL, Key::SPACE]),
+        0x01..=0x1a => {
+            keys.push(Key::CONTROL);
+            keys.extend(Key::from_char((b'a' + byte - 1) as char));
+        }
+        b'A'..=b'Z' => {
+            keys.push(Key::SHIFT);
+            keys.extend(Key::from_char(byte as char));
+        }
+        // Anything else (punctuation, UTF-8 continuation bytes) has no Key
+        _ => keys.extend(Key::from_char(byte as char)),
+    }
+}
+
+// Decodes the body of a `ESC [` sequence and returns how many bytes it used.
+fn decode_csi(bytes: &[u8], keys: &mut Vec<Key>) -> usize {
+    let end = match bytes.iter().position(|b| (0x40..=0x7e).contains(b)) {
+        Some(end) => end,
+        None => return bytes.len(),
+    };
+
+    let mut params = std::str::from_utf8(&bytes[..end])
+        .unwrap_or("")
+        .split(';')
+        .map(|param| param.parse::<u8>().unwrap_or(1));
+
+    let first = params.next().unwrap_or(1);
+    let modifiers = params.next().unwrap_or(1);
+
+    if bytes[end] == b'~' {
+        let key = match first {
+            1 | 7 => Some(Key::HOME),
+            2 => Some(Key::INSERT),
+            3 => Some(Key::DELETE),
+            4 | 8 => Some(Key::END),
+            5 => Some(Key::PRIOR),
+            6 => Some(Key::NEXT),
+            11..=15 => Key::function_key(first as usize - 10),
+            17..=21 => Key::function_key(first as usize - 11),
+            23 | 24 => Key::function_key(first as usize - 12),
+            _ => None,
+        };
+
+        if let Some(key) = key {
+            push_modifiers(modifiers, keys);
+            keys.push(key);
+        }
+    } else {
+        decode_final(bytes[end], modifiers, keys);
+    }
+
+    end + 1
+}
+
+fn decode_final(byte: u8, modifiers: u8, keys: &mut Vec<Key>) {
+    let key = match byte {
+        b'A' => Key::UP,
+        b'B' => Key::DOWN,
+        b'C' => Key::RIGHT,
+        b'D' => Key::LEFT,
+        b'H' => Key::HOME,
+        b'F' => Key::END,
+        b'P' => Key::F1,
+        b'Q' => Key::F2,
+        b'R' => Key::F3,
+        b'S' => Key::F4,
+        b'Z' => {
+            // Back tab
+            keys.extend_from_slice(&[Key::SHIFT, Key::TAB]);
+            return;
+        }
+        _ => return,
+    };
+
+    push_modifiers(modifiers, keys);
+    keys.push(key);
+}
+
+// xterm encodes modifiers as 1 + (shift 1 | alt 2 | ctrl 4)
+fn push_modifiers(modifiers: u8, keys: &mut Vec<Key>) {
+    let bits = modifiers.saturating_sub(1);
+
+    if bits & 1 != 0 {
+        keys.push(Key::SHIFT);
+    }
+    if bits & 2 != 0 {
+        keys.push(Key::MENU);
+    }
+    if bits & 4 != 0 {
+        keys.push(Key::CONTROL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_plain_keys() {
+        assert_eq!(decode(b"q"), vec![Key::CHAR_Q]);
+        assert_eq!(decode(b"Z"), vec![Key::SHIFT, Key::CHAR_Z]);
+        assert_eq!(
+            decode(b"7 \r\x7f"),
+            vec![Key::CHAR_7, Key::SPACE, Key::RETURN, Key::BACK]
+        );
+        assert_eq!(decode(b"\x03"), vec![Key::CONTROL, Key::CHAR_C]);
+        assert_eq!(decode(b"\x1b"), vec![Key::ESCAPE]);
+        assert_eq!(decode(b"\x1b\x1b"), vec![Key::ESCAPE, Key::ESCAPE]);
+        assert_eq!(decode(b"\x1bx"), vec![Key::MENU, Key::CHAR_X]);
+    }
+
+    #[test]
+    fn test_decode_escape_sequences() {
+        assert_eq!(
+            decode(b"\x1b[A\x1b[B\x1b[C\x1b[D"),
+            vec![Key::UP, Key::DOWN, Key::RIGHT, Key::LEFT]
+        );
+        assert_eq!(
+            decode(b"\x1bOP\x1b[15~\x1b[24~"),
+            vec![Key::F1, Key::F5, Key::F12]
+        );
+        assert_eq!(decode(b"\x1b[3~\x1b[5~"), vec![Key::DELETE, Key::PRIOR]);
+        assert_eq!(decode(b"\x1b[Z"), vec![Key::SHIFT, Key::TAB]);
+    }
+
+    #[test]
+    fn test_decode_modifiers() {
+        assert_eq!(decode(b"\x1b[1;5A"), vec![Key::CONTROL, Key::UP]);
+        assert_eq!(decode(b"\x1b[1;2D"), vec![Key::SHIFT, Key::LEFT]);
+        assert_eq!(
+            decode(b"\x1b[3;8~"),
+            vec![Key::SHIFT, Key::MENU, Key::CONTROL, Key::DELETE]
+        );
+    }
+}
@@ -0,0 +1,78 @@
+// Windows input backend.
+use super::Key;
+use winsafe::{co::VK, GetAsyncKeyState};
+
+pub fn is_key_down(key: Key) -> bool {
+    GetAsyncKeyState(to_vk(key))
+}
+
+fn to_vk(key: Key) -> VK {
+    match key {
+        Key::BACK => VK::BACK,
+        Key::TAB => VK::TAB,
+        Key::RETURN => VK::RETURN,
+        Key::SHIFT => VK::SHIFT,
+        Key::CONTROL => VK::CONTROL,
+        Key::MENU => VK::MENU,
+        Key::ESCAPE => VK::ESCAPE,
+        Key::SPACE => VK::SPACE,
+        Key::PRIOR => VK::PRIOR,
+        Key::NEXT => VK::NEXT,
+        Key::END => VK::END,
+        Key::HOME => VK::HOME,
+        Key::LEFT => VK::LEFT,
+        Key::UP => VK::UP,
+        Key::RIGHT => VK::RIGHT,
+        Key::DOWN => VK::DOWN,
+        Key::INSERT => VK::INSERT,
+        Key::DELETE => VK::DELETE,
+        Key::CHAR_0 => VK::CHAR_0,
+        Key::CHAR_1 => VK::CHAR_1,
+        Key::CHAR_2 => VK::CHAR_2,
+        Key::CHAR_3 => VK::CHAR_3,
+        Key::CHAR_4 => VK::CHAR_4,
+        Key::CHAR_5 => VK::CHAR_5,
+        Key::CHAR_6 => VK::CHAR_6,
+        Key::CHAR_7 => VK::CHAR_7,
+        Key::CHAR_8 => VK::CHAR_8,
+        Key::CHAR_9 => VK::CHAR_9,
+        Key::CHAR_A => VK::CHAR_A,
+        Key::CHAR_B => VK::CHAR_B,
+        Key::CHAR_C => VK::CHAR_C,
+        Key::CHAR_D => VK::CHAR_D,
+        Key::CHAR_E => VK::CHAR_E,
+        Key::CHAR_F => VK::CHAR_F,
+        Key::CHAR_G => VK::CHAR_G,
+        Key::CHAR_H => VK::CHAR_H,
+        Key::CHAR_I => VK::CHAR_I,
+        Key::CHAR_J => VK::CHAR_J,
+        Key::CHAR_K => VK::CHAR_K,
+        Key::CHAR_L => VK::CHAR_L,
+        Key::CHAR_M => VK::CHAR_M,
+        Key::CHAR_N => VK::CHAR_N,
+        Key::CHAR_O => VK::CHAR_O,
+        Key::CHAR_P => VK::CHAR_P,
+        Key::CHAR_Q => VK::CHAR_Q,
+        Key::CHAR_R => VK::CHAR_R,
+        Key::CHAR_S => VK::CHAR_S,
+        Key::CHAR_T => VK::CHAR_T,
+        Key::CHAR_U => VK::CHAR_U,
+        Key::CHAR_V => VK::CHAR_V,
+        Key::CHAR_W => VK::CHAR_W,
+        Key::CHAR_X => VK::CHAR_X,
+        Key::CHAR_Y => VK::CHAR_Y,
+        Key::CHAR_Z => VK::CHAR_Z,
+        Key::F1 => VK::F1,
+        Key::F2 => VK::F2,
+        Key::F3 => VK::F3,
+        Key::F4 => VK::F4,
+        Key::F5 => VK::F5,
+        Key::F6 => VK::F6,
+        Key::F7 => VK::F7,
+        Key::F8 => VK::F8,
+        Key::F9 => VK::F9,
+        Key::F10 => VK::F10,
+        Key::F11 => VK::F11,
+        Key::F12 => VK::F12,
+    }
+}
@@ -1,28 +1,208 @@
+pub mod animation;
+mod game;
+pub mod scene;
+pub mod tilemap;
+
+pub use game::{run, Config, Context, ControlFlow, Game, RunOptions};
+
 pub mod time {
-    use std::time::Instant;
+    use std::time::{Duration, Instant};
+
+    // Keeps to_fps() finite when two ticks land on the same Instant
+    const MIN_DELTA: f64 = 1e-9;
+    // Frames longer than this (a breakpoint, a dragged window) are clamped so a
+    // FixedStep doesn't try to catch up on all of it at once
+    const MAX_DELTA: f64 = 0.25;
+
+    /// Measures frame times; call `tick` once at the start of every frame.
+    pub struct Clock {
+        start: Instant,
+        last_tick: Instant,
+        delta: f64,
+    }
+
+    /// Runs a simulation in fixed-size steps, however long each frame takes.
+    ///
+    /// Each simulation owns its own FixedStep and feeds it the delta of every
+    /// frame it runs in, so time that passes while it isn't running (paused,
+    /// or not started yet) is never caught up on.
+    ///
+    /// ```
+    /// # use engine::time::{Clock, FixedStep};
+    /// let mut clock = Clock::new();
+    /// let mut physics = FixedStep::new(1.0 / 60.0);
+    ///
+    /// clock.tick();
+    ///
+    /// for _ in 0..physics.advance(clock.get_delta()).ticks {
+    ///     // advance the simulation by exactly 1/60th of a second
+    /// }
+    /// ```
+    pub struct FixedStep {
+        step: f64,
+        accumulator: f64,
+    }
+
+    /// The fixed-size steps that fit into the time accumulated so far.
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    pub struct FixedUpdates {
+        /// How many steps to run this frame.
+        pub ticks: u32,
+        /// How far into the next step the clock is, from 0.0 to 1.0; use it to
+        /// interpolate between the last two simulated states when rendering.
+        pub alpha: f64,
+    }
+
+    impl Clock {
+        pub fn new() -> Clock {
+            let now = Instant::now();
+
+            Clock {
+                start: now,
+                last_tick: now,
+                delta: MIN_DELTA,
+            }
+        }
+
+        /// Returns the seconds since the previous tick (or since the Clock was created).
+        pub fn tick(&mut self) -> f64 {
+            self.tick_at(Instant::now())
+        }
+
+        fn tick_at(&mut self, now: Instant) -> f64 {
+            self.delta = now
+                .duration_since(self.last_tick)
+                .as_secs_f64()
+                .max(MIN_DELTA);
+            self.last_tick = now;
+
+            self.delta
+        }
+
+        /// Returns the delta measured by the last tick.
+        pub fn get_delta(&self) -> f64 {
+            self.delta
+        }
+
+        /// Returns the seconds since the Clock was created.
+        pub fn get_elapsed(&self) -> f64 {
+            self.last_tick.duration_since(self.start).as_secs_f64()
+        }
+    }
+
+    impl Default for Clock {
+        fn default() -> Clock {
+            Clock::new()
+        }
+    }
+
+    impl FixedStep {
+        /// A FixedStep taking steps `step` seconds long.
+        pub fn new(step: f64) -> FixedStep {
+            FixedStep {
+                step,
+                accumulator: 0.0,
+            }
+        }
+
+        /// Adds a frame's `delta` and takes as many steps as have built up.
+        pub fn advance(&mut self, delta: f64) -> FixedUpdates {
+            self.accumulator += delta.min(MAX_DELTA);
+
+            let ticks = (self.accumulator / self.step).floor();
+            self.accumulator -= ticks * self.step;
+
+            FixedUpdates {
+                ticks: ticks as u32,
+                alpha: self.accumulator / self.step,
+            }
+        }
+
+        /// Throws away the time built up towards the next step.
+        pub fn reset(&mut self) {
+            self.accumulator = 0.0;
+        }
+    }
 
-    static mut TIME_SINCE_LAST_FRAME: f64 = 0.0;
+    /// Holds a loop to a target frame rate by sleeping for most of each frame's
+    /// spare time, then spinning for the last moment, which sleep can't hit precisely.
+    pub struct FramePacer {
+        frame_time: Duration,
+        spin_time: Duration,
+        frame_start: Instant,
+        stats: PacerStats,
+    }
 
-    pub fn update_delta() -> f64 {
-        let mut delta: f64;
+    /// How well the FramePacer has been keeping up.
+    #[derive(Debug, Copy, Clone, Default, PartialEq)]
+    pub struct PacerStats {
+        /// Seconds the last frame ran past its deadline.
+        pub overshoot: f64,
+        /// Frames that took so long the next one's deadline had passed too.
+        pub dropped_frames: u64,
+        pub frames: u64,
+    }
 
-        let current_time = Instant::now().elapsed().as_secs_f64();
+    impl FramePacer {
+        pub fn new(target_fps: f64) -> FramePacer {
+            FramePacer {
+                frame_time: Duration::from_secs_f64(1.0 / target_fps),
+                spin_time: Duration::from_millis(2),
+                frame_start: Instant::now(),
+                stats: PacerStats::default(),
+            }
+        }
 
-        unsafe {
-            delta = current_time - TIME_SINCE_LAST_FRAME;
-            TIME_SINCE_LAST_FRAME = current_time;
+        pub fn set_target_fps(&mut self, target_fps: f64) {
+            self.frame_time = Duration::from_secs_f64(1.0 / target_fps);
         }
 
-        if delta.is_sign_negative() {
-            delta = -delta;
+        /// Sets how much of the end of each frame is spun away rather than slept;
+        /// raise it on platforms with a coarse sleep timer.
+        pub fn set_spin_time(&mut self, spin_time: Duration) {
+            self.spin_time = spin_time;
         }
 
-        if delta == 0.0 {
-            // Make sure NaN is never returned in to_fps()
-            delta += 0.0000000000000000000001;
+        /// Waits until the current frame has taken the target frame time; call it
+        /// once at the end of every frame.
+        pub fn wait(&mut self) -> PacerStats {
+            let deadline = self.frame_start + self.frame_time;
+            let now = Instant::now();
+
+            if now < deadline {
+                let remaining = deadline - now;
+
+                if remaining > self.spin_time {
+                    std::thread::sleep(remaining - self.spin_time);
+                }
+
+                while Instant::now() < deadline {
+                    std::hint::spin_loop();
+                }
+            }
+
+            let now = Instant::now();
+            let overshoot = now - deadline;
+
+            self.stats.frames += 1;
+            self.stats.overshoot = overshoot.as_secs_f64();
+
+            if overshoot >= self.frame_time {
+                // Start again from now rather than rushing through the missed frames
+                self.stats.dropped_frames +=
+                    (overshoot.as_secs_f64() / self.frame_time.as_secs_f64()) as u64;
+                self.frame_start = now;
+            } else {
+                // Keep to the original cadence so small overshoots don't add up
+                self.frame_start = deadline;
+            }
+
+            self.stats
         }
 
-        delta
+        pub fn get_stats(&self) -> PacerStats {
+            self.stats
+        }
     }
 
     pub fn to_fps(delta: f64) -> f64 {
@@ -32,50 +212,293 @@ pub mod time {
     #[cfg(test)]
     mod tests {
         use super::*;
+        use std::time::Duration;
 
         #[test]
         fn test_delta_time() {
+            let mut clock = Clock::new();
+
             for _ in 0..10 {
-                let delta = update_delta();
+                std::thread::sleep(Duration::from_millis(2));
+
+                let delta = clock.tick();
+
+                assert!(delta >= 0.002);
 
                 let speed = 10.0;
 
                 let units_per_frame = speed * delta;
                 let units_per_second = units_per_frame * to_fps(delta);
 
-                assert!(units_per_second >= 9.5 && units_per_second <= 10.5);
+                assert!((9.5..=10.5).contains(&units_per_second));
+            }
+
+            assert!(clock.get_elapsed() >= 0.02);
+        }
+
+        #[test]
+        fn test_fixed_step() {
+            // 1/64th of a second, so every step is exact in floating point
+            let step = 0.015625;
+            let step_duration = Duration::from_nanos(15_625_000);
+
+            let mut clock = Clock::new();
+            let mut fixed_step = FixedStep::new(step);
+            let start = clock.last_tick;
+
+            clock.tick_at(start + step_duration * 5 / 2);
+            let updates = fixed_step.advance(clock.get_delta());
+
+            assert_eq!(updates.ticks, 2);
+            assert_eq!(updates.alpha, 0.5);
+
+            clock.tick_at(start + step_duration * 3);
+            let updates = fixed_step.advance(clock.get_delta());
+
+            assert_eq!(updates.ticks, 1);
+            assert_eq!(updates.alpha, 0.0);
+
+            // Long stalls are clamped to MAX_DELTA rather than replayed in full
+            clock.tick_at(start + step_duration * 1000);
+
+            assert_eq!(clock.get_delta(), step * 997.0);
+            assert_eq!(fixed_step.advance(clock.get_delta()).ticks, 16);
+
+            // Another simulation's steps don't take from this one's time
+            let mut other = FixedStep::new(step);
+
+            assert_eq!(other.advance(step * 1.5).ticks, 1);
+            assert_eq!(fixed_step.advance(step * 0.5).ticks, 0);
+            assert_eq!(fixed_step.advance(step * 0.5).ticks, 1);
+
+            fixed_step.advance(step * 0.5);
+            fixed_step.reset();
+
+            assert_eq!(fixed_step.advance(step * 0.5).alpha, 0.5);
+        }
+
+        #[test]
+        fn test_frame_pacer() {
+            let mut pacer = FramePacer::new(100.0);
+            let start = Instant::now();
+
+            for _ in 0..5 {
+                pacer.wait();
             }
+
+            let elapsed = start.elapsed();
+
+            assert!(elapsed >= Duration::from_millis(40));
+            assert!(elapsed < Duration::from_millis(100));
+            assert_eq!(pacer.get_stats().frames, 5);
+            assert_eq!(pacer.get_stats().dropped_frames, 0);
+
+            // A frame that takes 3.5 frames' worth of time drops the 2 after it
+            std::thread::sleep(Duration::from_millis(35));
+            let stats = pacer.wait();
+
+            assert!(stats.dropped_frames >= 2);
+            assert!(stats.overshoot >= 0.02);
         }
     }
 }
 
 pub mod input {
+    #[cfg(unix)]
+    mod unix;
+    #[cfg(windows)]
+    mod windows;
+
     use std::collections::hash_map::HashMap;
-    pub use winsafe::co::VK as Key;
-    use winsafe::GetAsyncKeyState;
+
+    /// Platform-neutral key codes; the names follow the Windows virtual key codes.
+    #[allow(non_camel_case_types)]
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub enum Key {
+        BACK,
+        TAB,
+        RETURN,
+        SHIFT,
+        CONTROL,
+        /// The ALT key.
+        MENU,
+        ESCAPE,
+        SPACE,
+        /// The PAGE UP key.
+        PRIOR,
+        /// The PAGE DOWN key.
+        NEXT,
+        END,
+        HOME,
+        LEFT,
+        UP,
+        RIGHT,
+        DOWN,
+        INSERT,
+        DELETE,
+        CHAR_0,
+        CHAR_1,
+        CHAR_2,
+        CHAR_3,
+        CHAR_4,
+        CHAR_5,
+        CHAR_6,
+        CHAR_7,
+        CHAR_8,
+        CHAR_9,
+        CHAR_A,
+        CHAR_B,
+        CHAR_C,
+        CHAR_D,
+        CHAR_E,
+        CHAR_F,
+        CHAR_G,
+        CHAR_H,
+        CHAR_I,
+        CHAR_J,
+        CHAR_K,
+        CHAR_L,
+        CHAR_M,
+        CHAR_N,
+        CHAR_O,
+        CHAR_P,
+        CHAR_Q,
+        CHAR_R,
+        CHAR_S,
+        CHAR_T,
+        CHAR_U,
+        CHAR_V,
+        CHAR_W,
+        CHAR_X,
+        CHAR_Y,
+        CHAR_Z,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+    }
+
+    impl Key {
+        const DIGITS: [Key; 10] = [
+            Key::CHAR_0,
+            Key::CHAR_1,
+            Key::CHAR_2,
+            Key::CHAR_3,
+            Key::CHAR_4,
+            Key::CHAR_5,
+            Key::CHAR_6,
+            Key::CHAR_7,
+            Key::CHAR_8,
+            Key::CHAR_9,
+        ];
+
+        const LETTERS: [Key; 26] = [
+            Key::CHAR_A,
+            Key::CHAR_B,
+            Key::CHAR_C,
+            Key::CHAR_D,
+            Key::CHAR_E,
+            Key::CHAR_F,
+            Key::CHAR_G,
+            Key::CHAR_H,
+            Key::CHAR_I,
+            Key::CHAR_J,
+            Key::CHAR_K,
+            Key::CHAR_L,
+            Key::CHAR_M,
+            Key::CHAR_N,
+            Key::CHAR_O,
+            Key::CHAR_P,
+            Key::CHAR_Q,
+            Key::CHAR_R,
+            Key::CHAR_S,
+            Key::CHAR_T,
+            Key::CHAR_U,
+            Key::CHAR_V,
+            Key::CHAR_W,
+            Key::CHAR_X,
+            Key::CHAR_Y,
+            Key::CHAR_Z,
+        ];
+
+        const FUNCTION_KEYS: [Key; 12] = [
+            Key::F1,
+            Key::F2,
+            Key::F3,
+            Key::F4,
+            Key::F5,
+            Key::F6,
+            Key::F7,
+            Key::F8,
+            Key::F9,
+            Key::F10,
+            Key::F11,
+            Key::F12,
+        ];
+
+        /// Returns the CHAR_ key for an ASCII letter or digit, ignoring case.
+        pub fn from_char(c: char) -> Option<Key> {
+            match c.to_ascii_uppercase() {
+                c @ '0'..='9' => Some(Key::DIGITS[c as usize - '0' as usize]),
+                c @ 'A'..='Z' => Some(Key::LETTERS[c as usize - 'A' as usize]),
+                ' ' => Some(Key::SPACE),
+                _ => None,
+            }
+        }
+
+        /// Returns F1 to F12 for `number` 1 to 12.
+        pub fn function_key(number: usize) -> Option<Key> {
+            number
+                .checked_sub(1)
+                .and_then(|index| Key::FUNCTION_KEYS.get(index))
+                .copied()
+        }
+    }
 
     pub struct Keyboard {
         keys: HashMap<Key, KeyState>,
+        #[cfg(unix)]
+        input: unix::RawInput,
     }
 
     impl Keyboard {
+        /// Creates the keyboard; on Unix this also puts the terminal into raw mode
+        /// until the Keyboard is dropped.
         pub fn create(keys_to_update: Vec<Key>) -> Keyboard {
             let mut keys: HashMap<Key, KeyState> = HashMap::new();
 
-            for vk in keys_to_update {
-                keys.insert(vk, KeyState::new());
+            for key in keys_to_update {
+                keys.insert(key, KeyState::new());
             }
 
-            Keyboard { keys: keys }
+            Keyboard {
+                keys,
+                #[cfg(unix)]
+                input: unix::RawInput::create(),
+            }
         }
 
+        #[cfg(windows)]
         pub fn update_key_states(&mut self) {
-            for key in self.keys.iter_mut() {
-                let (vk, state) = key;
+            for (key, state) in self.keys.iter_mut() {
+                state.update_state(windows::is_key_down(*key));
+            }
+        }
 
-                let key_state = GetAsyncKeyState(*vk);
+        #[cfg(unix)]
+        pub fn update_key_states(&mut self) {
+            let keys_down = self.input.poll();
 
-                state.update_state(key_state);
+            for (key, state) in self.keys.iter_mut() {
+                state.update_state(keys_down.contains(key));
             }
         }
 
@@ -196,12 +619,48 @@ pub mod input {
 }
 
 pub mod render {
+    mod ansi;
+    mod braille;
+    mod camera;
+    mod canvas;
+    mod cast;
+    mod font;
+    mod half_block;
+    mod headless;
+    mod image;
+    mod layer;
+    mod markup;
+    mod palette;
+    mod screenshot;
+    mod sprite;
+    mod style;
+    mod text;
+    #[cfg(windows)]
+    mod windows;
+
+    pub use ansi::AnsiBackend;
+    pub use braille::BrailleCanvas;
+    pub use camera::{Camera, Viewport};
+    pub use canvas::Canvas;
+    pub use cast::CastRecorder;
+    pub use half_block::{HalfBlockCanvas, HALF_BLOCK_LOWER, HALF_BLOCK_UPPER};
+    pub use headless::HeadlessBackend;
+    pub use image::Image;
+    pub use layer::Layer;
+    pub use markup::{parse_markup, MarkupError, TextRun};
+    pub use palette::{Colour, ColourDepth, ParseColourError};
+    pub use screenshot::ScreenshotFormat;
+    pub use sprite::Sprite;
+    pub use style::Style;
+    pub use text::{text_width, Align};
+    #[cfg(windows)]
+    pub use windows::WinConsoleBackend;
+
+    use palette::Quantiser;
     use std::error::Error;
-    pub use win32console::structs::char_info::CharInfo as Pixel;
-    use win32console::{
-        console::WinConsole,
-        structs::{coord::Coord, small_rect::SmallRect},
-    };
+    use std::fs::File;
+    use std::io::{self, BufWriter, ErrorKind, Write};
+    use std::path::Path;
 
     pub const PIXEL: char = '█';
     pub const PIXEL_THREE_QUARTERS: char = '▓';
@@ -211,15 +670,156 @@ pub mod render {
     pub const PIXEL_EMPTY: Pixel = Pixel {
         char_value: ' ',
         attributes: colour::FG_BLACK,
+        colours: None,
     };
 
+    /// Fills the cell to the right of a double-width character, which already
+    /// covers it; backends draw nothing for it.
+    pub const WIDE_CONTINUATION: char = '\0';
+
+    /// A single character cell; `attributes` holds the `colour` FG_/BG_ bits.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    pub struct Pixel {
+        pub char_value: char,
+        pub attributes: u16,
+        /// The foreground and background, for cells in more than the 16
+        /// colours; when set these are shown instead of the colours in
+        /// `attributes`, fitted to whatever the backend can show.
+        pub colours: Option<(Colour, Colour)>,
+    }
+
+    impl Pixel {
+        /// A Pixel in any style; `attributes` gets the nearest of the 16
+        /// colours to each of its colours.
+        pub fn new(char_value: char, style: Style) -> Pixel {
+            let colours = match (style.fg, style.bg) {
+                (Colour::Indexed(_), Colour::Indexed(_)) => None,
+                colours => Some(colours),
+            };
+
+            Pixel {
+                char_value,
+                attributes: style.to_attributes(),
+                colours,
+            }
+        }
+
+        pub fn get_style(&self) -> Style {
+            Style {
+                fg: self.get_fg(),
+                bg: self.get_bg(),
+                ..Style::from_attributes(self.attributes)
+            }
+        }
+
+        pub fn get_fg(&self) -> Colour {
+            match self.colours {
+                Some((fg, _)) => fg,
+                None => Colour::Indexed((self.attributes & 0x000F) as u8),
+            }
+        }
+
+        pub fn get_bg(&self) -> Colour {
+            match self.colours {
+                Some((_, bg)) => bg,
+                None => Colour::Indexed(((self.attributes & 0x00F0) >> 4) as u8),
+            }
+        }
+    }
+
+    /// Something that can show the frames drawn into a Console.
+    pub trait RenderBackend {
+        /// Presents a frame; `screen_buffer` is laid out in rows of `width` Pixels.
+        ///
+        /// `previous` is the last frame presented to this backend, if any, so that
+        /// backends can skip the cells that haven't changed since.
+        fn present(
+            &mut self,
+            screen_buffer: &[Pixel],
+            previous: Option<&[Pixel]>,
+            width: usize,
+        ) -> Result<FrameStats, Box<dyn Error>>;
+
+        /// How many colours the backend can show; Pixels in more colours than
+        /// this are fitted to them before they are presented.
+        fn get_colour_depth(&self) -> ColourDepth {
+            ColourDepth::Indexed16
+        }
+    }
+
+    /// How much output a backend produced for one presented frame.
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+    pub struct FrameStats {
+        pub cells_written: usize,
+        pub bytes_written: usize,
+    }
+
     pub struct Console {
         pub screen_buffer: Vec<Pixel>,
         screen_info: ScreenInfo,
-        console: WinConsole,
+        backend: Box<dyn RenderBackend>,
+        previous_frame: Option<Vec<Pixel>>,
+        frame_stats: FrameStats,
+        clip_stack: Vec<Rect>,
+        layers: Vec<Layer>,
+        // The layer drawing goes to, or the screen buffer when None
+        selected_layer: Option<usize>,
+        // The screen buffer with the layers composited over it, and its colours
+        // fitted to the backend
+        frame: Vec<Pixel>,
+        quantiser: Quantiser,
+        dithering: bool,
+        recorder: Option<CastRecorder>,
+    }
+
+    /// An area of the screen; `x` and `y` are the top left corner.
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+    pub struct Rect {
+        pub x: i32,
+        pub y: i32,
+        pub width: i32,
+        pub height: i32,
+    }
+
+    impl Rect {
+        pub fn new(x: i32, y: i32, width: i32, height: i32) -> Rect {
+            Rect {
+                x,
+                y,
+                width,
+                height,
+            }
+        }
+
+        /// A rect at (0, 0) covering a `width` by `height` grid, for checking
+        /// signed coordinates against; sizes over i32::MAX are clamped to it
+        /// rather than wrapping.
+        pub fn from_size(width: usize, height: usize) -> Rect {
+            let clamp = |size: usize| size.min(i32::MAX as usize) as i32;
+
+            Rect::new(0, 0, clamp(width), clamp(height))
+        }
+
+        pub fn contains(&self, x: i32, y: i32) -> bool {
+            x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+        }
+
+        /// Returns the area covered by both rects; empty if they don't overlap.
+        pub fn intersect(&self, other: &Rect) -> Rect {
+            let left = self.x.max(other.x);
+            let top = self.y.max(other.y);
+            let right = (self.x + self.width).min(other.x + other.width);
+            let bottom = (self.y + self.height).min(other.y + other.height);
+
+            Rect::new(left, top, (right - left).max(0), (bottom - top).max(0))
+        }
     }
 
     impl Console {
+        /// Creates the console on the platform's terminal and switches over to it.
+        ///
+        /// On Unix the font size can't be changed from inside the terminal, so
+        /// `font_width` and `font_height` are ignored there.
         pub fn create(
             width: u16,
             height: u16,
@@ -227,143 +827,426 @@ pub mod render {
             font_height: u16,
             title: &str,
         ) -> Result<Console, Box<dyn Error>> {
-            let (width, height) = (width as i16, height as i16);
-
-            let handle = WinConsole::create_console_screen_buffer()?;
-            let console = WinConsole::with_handle(handle);
-
-            WinConsole::set_active_console_screen_buffer(console.get_handle())?;
-            WinConsole::set_title(title)?;
-
-            // Set Window Size //////////
-            {
-                // In order to change the window size, you must set it to the minimum, set the SCREEN size,
-                // THEN set the window size to what you want.
-                console.set_window_info(
-                    true,
-                    &SmallRect {
-                        left: 0,
-                        top: 0,
-                        right: 1,
-                        bottom: 1,
-                    },
-                )?;
-
-                console.set_screen_buffer_size(Coord {
-                    x: width,
-                    y: height,
-                })?;
-
-                console.set_window_info(
-                    true,
-                    &SmallRect {
-                        left: 0,
-                        top: 0,
-                        right: width - 1,
-                        bottom: height - 1,
-                    },
-                )?;
-            }
-
-            // Set Font Size //////////
-            {
-                let mut new_font = console.get_font_ex(false)?;
-
-                new_font.font_size = Coord {
-                    x: font_width as i16,
-                    y: font_height as i16,
-                };
+            #[cfg(windows)]
+            let backend = WinConsoleBackend::create(width, height, font_width, font_height, title)?;
+
+            #[cfg(unix)]
+            let backend = {
+                let _ = (font_width, font_height);
+                AnsiBackend::create(title)?
+            };
+
+            Ok(Console::with_backend(width, height, Box::new(backend)))
+        }
 
-                console.set_font_ex(new_font, false)?;
+        /// Creates a console that presents its frames to `backend`.
+        pub fn with_backend(width: u16, height: u16, backend: Box<dyn RenderBackend>) -> Console {
+            let screen_buffer = vec![PIXEL_EMPTY; width as usize * height as usize];
+
+            let screen_info = ScreenInfo {
+                width: width as usize,
+                height: height as usize,
+            };
+
+            Console {
+                screen_buffer,
+                screen_info,
+                backend,
+                previous_frame: None,
+                frame_stats: FrameStats::default(),
+                clip_stack: Vec::new(),
+                layers: Vec::new(),
+                selected_layer: None,
+                frame: Vec::new(),
+                quantiser: Quantiser::new(),
+                dithering: false,
+                recorder: None,
             }
+        }
+
+        /// Whether cells of a single colour outside the backend's colours are
+        /// drawn as a shade glyph in two colours that mix to look like it,
+        /// rather than in the nearest colour; off to begin with. Colours
+        /// between two shades are ordered dithered across neighbouring cells.
+        pub fn set_dithering(&mut self, dithering: bool) {
+            self.dithering = dithering;
+        }
+
+        /// Presents the screen buffer with the visible layers over it; only the
+        /// cells that changed since the last call are sent to backends that support it.
+        pub fn update_screen(&mut self) -> Result<(), Box<dyn Error>> {
+            let width = self.screen_info.width;
+
+            let depth = self.backend.get_colour_depth();
+            let needs_fitting = depth < ColourDepth::TrueColour
+                && (self.screen_buffer.iter())
+                    .chain(self.layers.iter().flat_map(|layer| layer.pixels()))
+                    .any(|pixel| pixel.colours.is_some());
 
-            // Create Console
-            {
-                let mut screen_buffer: Vec<Pixel> = Vec::new();
+            let frame = if self.layers.is_empty() && !needs_fitting {
+                &self.screen_buffer
+            } else {
+                self.frame.clone_from(&self.screen_buffer);
+                layer::composite(&mut self.frame, &self.layers, width);
 
-                for _ in 0..width * height {
-                    screen_buffer.push(PIXEL_EMPTY);
+                if needs_fitting {
+                    for (index, pixel) in self.frame.iter_mut().enumerate() {
+                        let position = (index % width, index / width);
+
+                        *pixel = self.quantiser.fit(pixel, position, depth, self.dithering);
+                    }
                 }
 
-                let screen_info = ScreenInfo {
-                    area: SmallRect {
-                        left: 0,
-                        top: 0,
-                        right: width,
-                        bottom: height,
-                    },
-                    size: Coord {
-                        x: width,
-                        y: height,
-                    },
+                &self.frame
+            };
+
+            self.frame_stats =
+                self.backend
+                    .present(frame, self.previous_frame.as_deref(), width)?;
+
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record(frame, self.previous_frame.as_deref(), width)?;
+            }
+
+            match &mut self.previous_frame {
+                Some(previous) => previous.copy_from_slice(frame),
+                None => self.previous_frame = Some(frame.clone()),
+            }
+
+            Ok(())
+        }
+
+        /// Records every frame presented from now on, starting with a full one,
+        /// replacing any recording already in progress.
+        pub fn start_recording(&mut self, recorder: CastRecorder) {
+            self.recorder = Some(recorder);
+        }
+
+        /// Ends the recording, if there is one, and finishes its file.
+        pub fn stop_recording(&mut self) {
+            self.recorder = None;
+        }
+
+        /// Adds a transparent layer over the screen buffer, replacing any layer
+        /// that already has this name.
+        pub fn add_layer(&mut self, name: &str, z_index: i32) {
+            let layer = Layer::new(name, z_index, self.screen_buffer.len());
+
+            match self.find_layer(name) {
+                Some(index) => self.layers[index] = layer,
+                None => self.layers.push(layer),
+            }
+        }
+
+        /// Removes a layer; if it was selected, drawing goes back to the screen buffer.
+        pub fn remove_layer(&mut self, name: &str) {
+            if let Some(index) = self.find_layer(name) {
+                self.layers.remove(index);
+
+                self.selected_layer = match self.selected_layer {
+                    Some(selected) if selected == index => None,
+                    Some(selected) if selected > index => Some(selected - 1),
+                    selected => selected,
                 };
+            }
+        }
 
-                Ok(Console {
-                    screen_buffer: screen_buffer,
-                    console: console,
-                    screen_info: screen_info,
-                })
+        /// Sends all drawing, and `get_pixel`, to the named layer until another
+        /// one is selected.
+        pub fn select_layer(&mut self, name: &str) {
+            match self.find_layer(name) {
+                Some(index) => self.selected_layer = Some(index),
+                None => panic!("Layer was not found; did you add it with add_layer?"),
             }
         }
 
-        pub fn update_screen(&mut self) -> Result<(), Box<dyn Error>> {
-            self.console.write_output(
-                &self.screen_buffer,
-                self.screen_info.size,
-                Coord::ZERO,
-                self.screen_info.area,
-            )?;
+        /// Sends drawing back to the screen buffer, beneath all the layers.
+        pub fn select_base(&mut self) {
+            self.selected_layer = None;
+        }
+
+        pub fn get_layer(&self, name: &str) -> Option<&Layer> {
+            self.find_layer(name).map(|index| &self.layers[index])
+        }
+
+        pub fn get_layer_mut(&mut self, name: &str) -> Option<&mut Layer> {
+            self.find_layer(name)
+                .map(move |index| &mut self.layers[index])
+        }
+
+        fn find_layer(&self, name: &str) -> Option<usize> {
+            self.layers
+                .iter()
+                .position(|layer| layer.get_name() == name)
+        }
+
+        /// Forgets the last presented frame so the next update redraws every cell,
+        /// e.g. after something else has written to the terminal.
+        pub fn invalidate(&mut self) {
+            self.previous_frame = None;
+        }
+
+        /// Returns what the last call to `update_screen` wrote.
+        pub fn get_frame_stats(&self) -> FrameStats {
+            self.frame_stats
+        }
+
+        /// Draws a Pixel; anything outside the console or the current clip
+        /// rectangle is skipped, so partly off-screen shapes are safe to draw.
+        pub fn draw_pixel(&mut self, x: i32, y: i32, pixel: &Pixel) {
+            if !self.get_clip().contains(x, y) {
+                return;
+            }
+
+            let width = self.screen_info.width;
+            let index = y as usize * width + x as usize;
+
+            match self.selected_layer {
+                Some(layer) => self.layers[layer].pixels_mut()[index] = *pixel,
+                None => self.screen_buffer[index] = *pixel,
+            }
+        }
+
+        /// Draws a line of text; double-width characters take up two cells, and
+        /// zero-width ones such as combining marks are left out.
+        pub fn draw_string(&mut self, x: i32, y: i32, string: &str, style: Style) {
+            let mut x = x;
+
+            for c in string.chars() {
+                x += self.draw_char(x, y, c, style);
+            }
+        }
+
+        /// Draws a line of text with inline colour tags, starting out in `style`;
+        /// see `parse_markup` for the tags. Nothing is drawn if the markup is invalid.
+        pub fn draw_markup(
+            &mut self,
+            x: i32,
+            y: i32,
+            markup: &str,
+            style: Style,
+        ) -> Result<(), MarkupError> {
+            let mut x = x;
+
+            for run in parse_markup(markup, style)? {
+                self.draw_string(x, y, &run.text, run.style);
+                x += text_width(&run.text) as i32;
+            }
 
             Ok(())
         }
 
-        pub fn draw_pixel(&mut self, x: usize, y: usize, pixel: &Pixel) {
-            let width = self.screen_info.size.x as usize;
-            let index = y * width + x;
+        /// Draws text inside `rect`, wrapped at spaces and aligned on each line.
+        /// Text that doesn't fit ends with an ellipsis on the last line.
+        pub fn draw_text(&mut self, rect: Rect, string: &str, style: Style, align: Align) {
+            if rect.width <= 0 || rect.height <= 0 {
+                return;
+            }
+
+            let (width, height) = (rect.width as usize, rect.height as usize);
+            let mut lines = text::wrap(string, width);
+
+            if lines.len() > height {
+                lines.truncate(height);
+                lines[height - 1] = text::ellipsize(&lines[height - 1], width);
+            }
 
-            self.screen_buffer[index] = *pixel;
+            self.push_clip(rect);
+
+            for (row, line) in lines.iter().enumerate() {
+                // A wide character wrapped on its own can still be wider than the rect
+                let spare = width.saturating_sub(text_width(line)) as i32;
+                let offset = match align {
+                    Align::Left => 0,
+                    Align::Centre => spare / 2,
+                    Align::Right => spare,
+                };
+
+                self.draw_string(rect.x + offset, rect.y + row as i32, line, style);
+            }
+
+            self.pop_clip();
         }
 
-        pub fn draw_string(&mut self, x: usize, y: usize, string: &str, colour: u16) {
-            let chars = string.as_bytes();
+        // Draws one character and returns how many columns it took up
+        fn draw_char(&mut self, x: i32, y: i32, c: char, style: Style) -> i32 {
+            let width = text::char_width(c) as i32;
+            let pixel = Pixel::new(c, style);
+
+            match width {
+                0 => {}
+                1 => self.draw_pixel(x, y, &pixel),
+                _ => {
+                    let clip = self.get_clip();
+
+                    // Half a wide character can't be shown, so blank out the
+                    // visible half instead
+                    if clip.contains(x, y) && clip.contains(x + 1, y) {
+                        self.draw_pixel(x, y, &pixel);
+                        self.draw_pixel(
+                            x + 1,
+                            y,
+                            &Pixel {
+                                char_value: WIDE_CONTINUATION,
+                                ..pixel
+                            },
+                        );
+                    } else {
+                        let blank = Pixel {
+                            char_value: ' ',
+                            ..pixel
+                        };
+
+                        self.draw_pixel(x, y, &blank);
+                        self.draw_pixel(x + 1, y, &blank);
+                    }
+                }
+            }
 
-            let mut pixel = PIXEL_EMPTY;
+            width
+        }
 
-            for (str_x, c) in chars.iter().enumerate() {
-                pixel.char_value = *c as char;
-                pixel.attributes = colour;
+        /// Fills everything from (x, y) to the bottom right corner.
+        pub fn fill(&mut self, x: i32, y: i32, pixel: &Pixel) {
+            let (width, height) = (self.get_width() as i32, self.get_height() as i32);
+            let area = self
+                .get_clip()
+                .intersect(&Rect::new(x, y, width - x, height - y));
 
-                self.draw_pixel(str_x as usize + x, y, &pixel);
+            for screen_y in area.y..area.y + area.height {
+                for screen_x in area.x..area.x + area.width {
+                    self.draw_pixel(screen_x, screen_y, pixel);
+                }
             }
         }
 
-        pub fn fill(&mut self, x: usize, y: usize, pixel: &Pixel) {
-            for screen_x in x..self.get_width() {
-                for screen_y in y..self.get_height() {
-                    self.draw_pixel(screen_x, screen_y, &pixel);
+        /// Draws a whole Sprite with its top left corner at (x, y), leaving its
+        /// transparent cells alone.
+        pub fn draw_sprite(&mut self, x: i32, y: i32, sprite: &Sprite) {
+            let whole = Rect::new(0, 0, sprite.get_width() as i32, sprite.get_height() as i32);
+
+            self.draw_partial_sprite(x, y, sprite, whole);
+        }
+
+        /// Draws the `source` area of a Sprite with its top left corner at (x, y).
+        pub fn draw_partial_sprite(&mut self, x: i32, y: i32, sprite: &Sprite, source: Rect) {
+            let whole = Rect::new(0, 0, sprite.get_width() as i32, sprite.get_height() as i32);
+            let area = source.intersect(&whole);
+
+            // Keep the drawn part in place when the source rect hangs off the sprite
+            let (x, y) = (x + area.x - source.x, y + area.y - source.y);
+
+            for sprite_y in 0..area.height {
+                for sprite_x in 0..area.width {
+                    let pixel = sprite
+                        .get_pixel((area.x + sprite_x) as usize, (area.y + sprite_y) as usize);
+
+                    if pixel.char_value != ' ' {
+                        self.draw_pixel(x + sprite_x, y + sprite_y, &pixel);
+                    }
                 }
             }
         }
 
+        /// Restricts drawing to `rect`, within whatever clip rectangle is already active.
+        pub fn push_clip(&mut self, rect: Rect) {
+            let clip = self.get_clip().intersect(&rect);
+
+            self.clip_stack.push(clip);
+        }
+
+        /// Goes back to the clip rectangle that was active before the last `push_clip`.
+        pub fn pop_clip(&mut self) {
+            self.clip_stack.pop();
+        }
+
+        /// Returns the area drawing is currently limited to.
+        pub fn get_clip(&self) -> Rect {
+            match self.clip_stack.last() {
+                Some(clip) => *clip,
+                None => Rect::new(
+                    0,
+                    0,
+                    self.screen_info.width as i32,
+                    self.screen_info.height as i32,
+                ),
+            }
+        }
+
+        /// Returns the Pixel at (x, y) in the selected layer or the screen buffer.
         pub fn get_pixel(&self, x: usize, y: usize) -> Pixel {
-            let width = self.screen_info.size.x as usize;
+            let width = self.screen_info.width;
             let index = y * width + x;
 
-            self.screen_buffer[index]
+            match self.selected_layer {
+                Some(layer) => self.layers[layer].pixels()[index],
+                None => self.screen_buffer[index],
+            }
+        }
+
+        // The background of cell (x, y) in the buffer drawing goes to, or
+        // black off the screen; for canvases that draw over what's there
+        pub(crate) fn get_background(&self, x: i32, y: i32) -> Colour {
+            if !Rect::from_size(self.get_width(), self.get_height()).contains(x, y) {
+                return Colour::BLACK;
+            }
+
+            self.get_pixel(x as usize, y as usize).get_bg()
         }
 
         pub fn get_width(&self) -> usize {
-            self.screen_info.size.x as usize
+            self.screen_info.width
         }
 
         pub fn get_height(&self) -> usize {
-            self.screen_info.size.y as usize
+            self.screen_info.height
+        }
+
+        /// Writes what the player sees, the screen buffer with the visible
+        /// layers over it, in full colour whatever the backend can show.
+        pub fn write_screenshot<W: Write>(
+            &self,
+            format: ScreenshotFormat,
+            out: &mut W,
+        ) -> io::Result<()> {
+            let width = self.screen_info.width;
+            let mut frame = self.screen_buffer.clone();
+
+            layer::composite(&mut frame, &self.layers, width);
+
+            screenshot::write_screenshot(&frame, width, format, out)
+        }
+
+        /// Saves a screenshot in the format picked by the path's extension; see
+        /// `ScreenshotFormat::from_path`.
+        pub fn save_screenshot<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+            let format = ScreenshotFormat::from_path(&path).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("no screenshot format for '{}'", path.as_ref().display()),
+                )
+            })?;
+            let mut writer = BufWriter::new(File::create(path)?);
+
+            self.write_screenshot(format, &mut writer)?;
+            writer.flush()?;
+
+            Ok(())
+        }
+    }
+
+    // Gives the Console draw_line, fill_rect, draw_circle and the rest
+    impl Canvas for Console {
+        type Ink = Pixel;
+
+        fn plot(&mut self, x: i32, y: i32, pixel: &Pixel) {
+            self.draw_pixel(x, y, pixel);
         }
     }
 
     struct ScreenInfo {
-        area: SmallRect,
-        size: Coord,
+        width: usize,
+        height: usize,
     }
 
     pub mod colour {
@@ -400,6 +1283,12 @@ pub mod render {
         pub const BG_MAGENTA: u16 = 0x00D0;
         pub const BG_YELLOW: u16 = 0x00E0;
         pub const BG_WHITE: u16 = 0x00F0;
+
+        // The Windows console's COMMON_LVB_REVERSE_VIDEO and COMMON_LVB_UNDERSCORE
+        pub const REVERSE: u16 = 0x4000;
+        pub const UNDERLINE: u16 = 0x8000;
+        // A bit the Windows console doesn't use, so it can't show bold
+        pub const BOLD: u16 = 0x2000;
     }
 
     #[cfg(test)]
@@ -409,11 +1298,16 @@ pub mod render {
         const PIXEL_WHITE: Pixel = Pixel {
             char_value: PIXEL,
             attributes: colour::FG_WHITE,
+            colours: None,
         };
 
+        fn headless_console() -> Console {
+            Console::with_backend(80, 30, Box::new(HeadlessBackend::new()))
+        }
+
         #[test]
         fn test_console() {
-            let mut console = Console::create(80, 30, 16, 16, "SPRITE TEST").unwrap();
+            let mut console = headless_console();
 
             // Check that the Sprite was created successfully
             for x in 0..console.get_width() {
@@ -432,21 +1326,176 @@ pub mod render {
 
         #[test]
         fn test_draw_string() {
-            let mut console = Console::create(80, 30, 16, 16, "SPRITE TEST").unwrap();
-            console.draw_string(1, 1, "A", colour::FG_WHITE);
+            let mut console = headless_console();
+            console.draw_string(1, 1, "A", Style::fg(Colour::WHITE));
 
             assert_eq!(
                 console.get_pixel(1, 1),
                 Pixel {
                     char_value: 'A',
-                    attributes: colour::FG_WHITE
+                    attributes: colour::FG_WHITE,
+                    colours: None,
                 }
             );
+
+            console.draw_string(0, 2, "█日e\u{301}!", Style::fg(Colour::WHITE));
+
+            let row: String = (0..5).map(|x| console.get_pixel(x, 2).char_value).collect();
+            assert_eq!(row, "█日\0e!");
+
+            // A wide character cut in half by the edge is blanked out
+            console.draw_string(79, 3, "日", Style::fg(Colour::WHITE));
+            assert_eq!(console.get_pixel(79, 3).char_value, ' ');
+        }
+
+        #[test]
+        fn test_layers() {
+            let backend = HeadlessBackend::new();
+            let mut console = Console::with_backend(4, 1, Box::new(backend.clone()));
+            let pixel = |char_value| Pixel {
+                char_value,
+                attributes: colour::FG_WHITE,
+                colours: None,
+            };
+
+            console.draw_string(0, 0, "abcd", Style::fg(Colour::WHITE));
+
+            console.add_layer("top", 2);
+            console.add_layer("middle", 1);
+            console.select_layer("top");
+            console.draw_string(0, 0, "T", Style::fg(Colour::WHITE));
+            console.select_layer("middle");
+            console.draw_string(0, 0, "MM", Style::fg(Colour::WHITE));
+
+            // Drawing went to the layers, not the screen buffer
+            assert_eq!(console.get_pixel(1, 0), pixel('M'));
+            console.select_base();
+            assert_eq!(console.get_pixel(1, 0), pixel('b'));
+
+            console.update_screen().unwrap();
+            assert_eq!(
+                backend.last_frame().unwrap(),
+                "TMcd".chars().map(pixel).collect::<Vec<_>>()
+            );
+
+            // With 'M' as its key, the middle layer's blank cells are no longer
+            // transparent, and now hide the top layer's T
+            let top = console.get_layer_mut("top").unwrap();
+            top.set_offset(3, 0);
+            let middle = console.get_layer_mut("middle").unwrap();
+            middle.set_transparent_key(pixel('M'));
+            middle.set_z_index(3);
+
+            console.update_screen().unwrap();
+            assert_eq!(
+                backend.last_frame().unwrap(),
+                [pixel('a'), pixel('b'), PIXEL_EMPTY, PIXEL_EMPTY]
+            );
+
+            console.get_layer_mut("middle").unwrap().clear();
+
+            console.update_screen().unwrap();
+            assert_eq!(
+                backend.last_frame().unwrap(),
+                "abcT".chars().map(pixel).collect::<Vec<_>>()
+            );
+
+            console.get_layer_mut("top").unwrap().set_visible(false);
+            console.select_layer("middle");
+            console.remove_layer("middle");
+            console.draw_pixel(1, 0, &pixel('x'));
+
+            console.update_screen().unwrap();
+            assert_eq!(
+                backend.last_frame().unwrap(),
+                "axcd".chars().map(pixel).collect::<Vec<_>>()
+            );
+            assert!(console.get_layer("middle").is_none());
+        }
+
+        #[test]
+        fn test_draw_markup() {
+            let mut console = Console::with_backend(8, 1, Box::new(HeadlessBackend::new()));
+
+            console
+                .draw_markup(0, 0, "日[fg=red]a[bg=blue]b[/]c", Style::fg(Colour::WHITE))
+                .unwrap();
+
+            let cells: Vec<(char, u16)> = (0..5)
+                .map(|x| {
+                    let pixel = console.get_pixel(x, 0);
+                    (pixel.char_value, pixel.attributes)
+                })
+                .collect();
+
+            assert_eq!(
+                cells,
+                [
+                    ('日', colour::FG_WHITE),
+                    (WIDE_CONTINUATION, colour::FG_WHITE),
+                    ('a', colour::FG_RED),
+                    ('b', colour::FG_RED | colour::BG_BLUE),
+                    ('c', colour::FG_RED),
+                ]
+            );
+
+            // Bad markup is reported instead of being drawn
+            console.fill(0, 0, &PIXEL_EMPTY);
+
+            assert!(console
+                .draw_markup(0, 0, "ok [oops]", Style::default())
+                .is_err());
+            assert_eq!(console.screen_buffer, vec![PIXEL_EMPTY; 8]);
+        }
+
+        #[test]
+        fn test_draw_text() {
+            let mut console = Console::with_backend(12, 4, Box::new(HeadlessBackend::new()));
+            let row = |console: &Console, y| -> String {
+                (0..12)
+                    .map(|x| console.get_pixel(x, y).char_value)
+                    .collect()
+            };
+
+            let text = "The quick brown fox jumps over the lazy dog";
+
+            console.draw_text(
+                Rect::new(1, 0, 10, 2),
+                text,
+                Style::fg(Colour::WHITE),
+                Align::Left,
+            );
+            assert_eq!(row(&console, 0), " The quick  ");
+            assert_eq!(row(&console, 1), " brown fox… ");
+
+            console.draw_text(
+                Rect::new(0, 2, 12, 1),
+                "centre",
+                Style::fg(Colour::WHITE),
+                Align::Centre,
+            );
+            console.draw_text(
+                Rect::new(0, 3, 12, 1),
+                "right",
+                Style::fg(Colour::WHITE),
+                Align::Right,
+            );
+            assert_eq!(row(&console, 2), "   centre   ");
+            assert_eq!(row(&console, 3), "       right");
+
+            // A wide character can't fit in one column, so it's blanked out
+            console.draw_text(
+                Rect::new(1, 0, 1, 1),
+                "日",
+                Style::fg(Colour::WHITE),
+                Align::Right,
+            );
+            assert_eq!(row(&console, 0), "  he quick  ");
         }
 
         #[test]
         fn test_fill() {
-            let mut console = Console::create(80, 30, 16, 16, "SPRITE TEST").unwrap();
+            let mut console = headless_console();
             console.fill(0, 0, &PIXEL_WHITE);
 
             for x in 0..console.get_width() {
@@ -455,5 +1504,158 @@ pub mod render {
                 }
             }
         }
+
+        #[test]
+        fn test_update_screen() {
+            let backend = HeadlessBackend::new();
+            let mut console = Console::with_backend(4, 2, Box::new(backend.clone()));
+
+            console.update_screen().unwrap();
+            console.draw_pixel(3, 1, &PIXEL_WHITE);
+            console.update_screen().unwrap();
+
+            assert_eq!(backend.frame_count(), 2);
+            assert_eq!(backend.frame(0).unwrap(), vec![PIXEL_EMPTY; 8]);
+
+            let last_frame = backend.last_frame().unwrap();
+            assert_eq!(last_frame[7], PIXEL_WHITE);
+            assert_eq!(last_frame[..7], [PIXEL_EMPTY; 7]);
+        }
+
+        #[test]
+        fn test_update_screen_fits_colours() {
+            let mut backend = HeadlessBackend::new();
+            backend.set_colour_depth(ColourDepth::Indexed16);
+
+            let mut console = Console::with_backend(2, 1, Box::new(backend.clone()));
+            let dark_red = Pixel::new(PIXEL, Style::fg(Colour::Rgb(64, 0, 0)));
+
+            console.draw_pixel(0, 0, &dark_red);
+            console.update_screen().unwrap();
+
+            let frame = backend.last_frame().unwrap();
+            assert_eq!(frame[0].colours, None);
+            assert_eq!(frame[0].char_value, PIXEL);
+
+            // Dithering mixes two colours with a shade glyph instead
+            console.set_dithering(true);
+            console.update_screen().unwrap();
+
+            let frame = backend.last_frame().unwrap();
+            assert_eq!(frame[0].char_value, PIXEL_HALF);
+            assert_eq!(frame[0].attributes, colour::FG_BLACK | colour::BG_DARK_RED);
+            assert_eq!(frame[1], PIXEL_EMPTY);
+
+            // The screen buffer keeps the colours it was drawn in
+            assert_eq!(console.get_pixel(0, 0), dark_red);
+        }
+
+        #[test]
+        fn test_draw_out_of_bounds() {
+            let mut console = Console::with_backend(4, 2, Box::new(HeadlessBackend::new()));
+
+            console.draw_pixel(4, 0, &PIXEL_WHITE);
+            console.draw_pixel(0, 2, &PIXEL_WHITE);
+            console.draw_pixel(-1, 0, &PIXEL_WHITE);
+            console.draw_pixel(0, -1, &PIXEL_WHITE);
+
+            // Nothing wraps round onto the next row
+            assert_eq!(console.screen_buffer, vec![PIXEL_EMPTY; 8]);
+
+            console.draw_string(-2, 1, "ABCDEFG", Style::fg(Colour::WHITE));
+
+            assert_eq!(console.get_pixel(0, 1).char_value, 'C');
+            assert_eq!(console.get_pixel(3, 1).char_value, 'F');
+            assert_eq!(console.get_pixel(0, 0), PIXEL_EMPTY);
+        }
+
+        #[test]
+        fn test_clip() {
+            let mut console = Console::with_backend(8, 8, Box::new(HeadlessBackend::new()));
+
+            console.push_clip(Rect::new(2, 2, 4, 4));
+            console.push_clip(Rect::new(4, -10, 10, 20));
+
+            assert_eq!(console.get_clip(), Rect::new(4, 2, 2, 4));
+
+            console.fill(0, 0, &PIXEL_WHITE);
+            console.pop_clip();
+            console.draw_pixel(2, 2, &PIXEL_WHITE);
+            console.draw_pixel(1, 1, &PIXEL_WHITE);
+            console.pop_clip();
+
+            assert_eq!(console.get_clip(), Rect::new(0, 0, 8, 8));
+
+            for x in 0..8 {
+                for y in 0..8 {
+                    let expected =
+                        if Rect::new(4, 2, 2, 4).contains(x as i32, y as i32) || (x, y) == (2, 2) {
+                            PIXEL_WHITE
+                        } else {
+                            PIXEL_EMPTY
+                        };
+
+                    assert_eq!(console.get_pixel(x, y), expected);
+                }
+            }
+        }
+
+        #[test]
+        fn test_shapes() {
+            let mut console = Console::with_backend(8, 8, Box::new(HeadlessBackend::new()));
+
+            // Shapes hanging off the edges, or outside the clip, are cut off
+            console.push_clip(Rect::new(0, 0, 8, 4));
+            console.fill_circle(0, 0, 20, &PIXEL_WHITE);
+            console.pop_clip();
+            console.draw_line(-4, 7, 20, 7, &PIXEL_WHITE);
+            console.draw_triangle(2, 5, 9, 5, 2, 100, &PIXEL_WHITE);
+
+            for x in 0..8 {
+                for y in 0..8 {
+                    let expected = if y < 4 || y == 7 || (y == 5 && x >= 2) || (y > 5 && x == 2) {
+                        PIXEL_WHITE
+                    } else {
+                        PIXEL_EMPTY
+                    };
+
+                    assert_eq!(console.get_pixel(x, y), expected, "at {}, {}", x, y);
+                }
+            }
+        }
+
+        #[test]
+        fn test_draw_sprite() {
+            let mut console = Console::with_backend(4, 4, Box::new(HeadlessBackend::new()));
+            let mut sprite = Sprite::new(3, 3);
+
+            // Leave the centre transparent
+            sprite.fill_rect(0, 0, 3, 3, &PIXEL_WHITE);
+            sprite.set_pixel(1, 1, &PIXEL_EMPTY);
+
+            let red = Pixel {
+                char_value: 'R',
+                attributes: colour::FG_RED,
+                colours: None,
+            };
+
+            console.fill(0, 0, &red);
+            console.draw_sprite(-1, -1, &sprite);
+
+            assert_eq!(console.get_pixel(0, 0), red);
+            assert_eq!(console.get_pixel(1, 0), PIXEL_WHITE);
+            assert_eq!(console.get_pixel(0, 1), PIXEL_WHITE);
+            assert_eq!(console.get_pixel(2, 2), red);
+
+            // Only the bottom right 2x2, with the source rect hanging off the sprite
+            console.fill(0, 0, &red);
+            console.draw_partial_sprite(2, 2, &sprite, Rect::new(0, 0, 5, 5));
+            console.draw_partial_sprite(0, 0, &sprite, Rect::new(1, 1, 2, 2));
+
+            assert_eq!(console.get_pixel(0, 0), red);
+            assert_eq!(console.get_pixel(1, 1), PIXEL_WHITE);
+            assert_eq!(console.get_pixel(2, 2), PIXEL_WHITE);
+            assert_eq!(console.get_pixel(3, 3), red);
+        }
     }
 }
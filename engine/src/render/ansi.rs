@@ -0,0 +1,536 @@
+// ANSI/VT backend; the default on Unix terminals.
+use super::palette::indexed_to_ansi;
+use super::text::{cell_glyph, char_width};
+use super::{Colour, ColourDepth, FrameStats, Pixel, RenderBackend, Style, WIDE_CONTINUATION};
+use std::error::Error;
+use std::io::{self, Write};
+
+// Switch to the alternate screen, hide the cursor and clear it
+const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l\x1b[2J";
+// Reset colours, show the cursor and go back to the normal screen
+const LEAVE_SCREEN: &str = "\x1b[0m\x1b[?25h\x1b[?1049l";
+
+// Unchanged cells up to this long are reprinted rather than jumped over, as
+// long as they don't need a colour change; "\x1b[nC" is at least 3 bytes.
+const MAX_REPRINT_GAP: usize = 3;
+
+pub struct AnsiBackend {
+    out: Box<dyn Write>,
+    encoder: AnsiEncoder,
+    buffer: Vec<u8>,
+    colour_depth: ColourDepth,
+}
+
+impl AnsiBackend {
+    /// Switches stdout to the alternate screen; it is switched back when dropped.
+    pub fn create(title: &str) -> io::Result<AnsiBackend> {
+        AnsiBackend::with_writer(Box::new(io::stdout()), title)
+    }
+
+    /// Like `create`, but writes the escape sequences to `out` instead of stdout.
+    pub fn with_writer(mut out: Box<dyn Write>, title: &str) -> io::Result<AnsiBackend> {
+        write!(out, "{}\x1b]0;{}\x07", ENTER_SCREEN, title)?;
+        out.flush()?;
+
+        Ok(AnsiBackend {
+            out,
+            encoder: AnsiEncoder::new(),
+            buffer: Vec::new(),
+            colour_depth: ColourDepth::detect(),
+        })
+    }
+
+    /// Overrides the colour depth guessed from the environment.
+    pub fn set_colour_depth(&mut self, colour_depth: ColourDepth) {
+        self.colour_depth = colour_depth;
+    }
+}
+
+impl RenderBackend for AnsiBackend {
+    fn present(
+        &mut self,
+        screen_buffer: &[Pixel],
+        previous: Option<&[Pixel]>,
+        width: usize,
+    ) -> Result<FrameStats, Box<dyn Error>> {
+        self.buffer.clear();
+
+        let cells_written =
+            self.encoder
+                .encode(screen_buffer, previous, width, &mut self.buffer)?;
+
+        self.out.write_all(&self.buffer)?;
+        self.out.flush()?;
+
+        Ok(FrameStats {
+            cells_written,
+            bytes_written: self.buffer.len(),
+        })
+    }
+
+    fn get_colour_depth(&self) -> ColourDepth {
+        self.colour_depth
+    }
+}
+
+impl Drop for AnsiBackend {
+    fn drop(&mut self) {
+        let _ = self.out.write_all(LEAVE_SCREEN.as_bytes());
+        let _ = self.out.flush();
+    }
+}
+
+/// Turns frames into the escape sequences that draw them, remembering where the
+/// cursor was left and which style is active so it's only sent when needed.
+pub(crate) struct AnsiEncoder {
+    cursor: Option<(usize, usize)>,
+    style: Option<Style>,
+}
+
+impl AnsiEncoder {
+    pub fn new() -> AnsiEncoder {
+        AnsiEncoder {
+            cursor: None,
+            style: None,
+        }
+    }
+
+    /// Writes the cells of `screen_buffer` that differ from `previous` (or every
+    /// cell, without one) and returns how many cells were written.
+    pub fn encode(
+        &mut self,
+        screen_buffer: &[Pixel],
+        previous: Option<&[Pixel]>,
+        width: usize,
+        out: &mut Vec<u8>,
+    ) -> io::Result<usize> {
+        let differs = |index: usize| match previous {
+            Some(previous) => previous[index] != screen_buffer[index],
+            None => true,
+        };
+
+        // The two halves of a wide character are redrawn together
+        let is_changed = |index: usize| {
+            let (x, c) = (index % width, screen_buffer[index].char_value);
+
+            differs(index)
+                || (x + 1 < width && differs(index + 1) && char_width(c) == 2)
+                || (x > 0 && differs(index - 1) && c == WIDE_CONTINUATION)
+        };
+
+        let mut cells_written = 0;
+
+        for (index, pixel) in screen_buffer.iter().enumerate() {
+            if !is_changed(index) {
+                continue;
+            }
+
+            // The right half of a wide character was printed along with the left
+            let glyph = match cell_glyph(screen_buffer, index, width) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            let glyph_width = char_width(glyph);
+
+            let (x, y) = (index % width, index / width);
+
+            if let Some((cursor_x, cursor_y)) = self.cursor {
+                if cursor_y == y && cursor_x < x {
+                    let gap = &screen_buffer[index - (x - cursor_x)..index];
+
+                    if gap.len() <= MAX_REPRINT_GAP
+                        && gap.iter().all(|cell| {
+                            char_width(cell.char_value) == 1 && self.matches_style(cell)
+                        })
+                    {
+                        for cell in gap {
+                            write!(out, "{}", cell.char_value)?;
+                        }
+
+                        cells_written += gap.len();
+                        self.cursor = Some((x, y));
+                    }
+                }
+            }
+
+            self.move_cursor(x, y, out)?;
+            self.set_style(pixel, out)?;
+
+            write!(out, "{}", glyph)?;
+            cells_written += glyph_width;
+
+            // The cursor stays on the last column once a row is full, so its position
+            // is only known again after the next explicit move
+            self.cursor = if x + glyph_width < width {
+                Some((x + glyph_width, y))
+            } else {
+                None
+            };
+        }
+
+        Ok(cells_written)
+    }
+
+    fn move_cursor(&mut self, x: usize, y: usize, out: &mut Vec<u8>) -> io::Result<()> {
+        match self.cursor {
+            Some(cursor) if cursor == (x, y) => Ok(()),
+            Some((cursor_x, cursor_y)) if cursor_y == y && cursor_x < x => match x - cursor_x {
+                1 => write!(out, "\x1b[C"),
+                n => write!(out, "\x1b[{}C", n),
+            },
+            // Terminal rows and columns are 1-based
+            _ if x == 0 => write!(out, "\x1b[{}H", y + 1),
+            _ => write!(out, "\x1b[{};{}H", y + 1, x + 1),
+        }
+    }
+
+    // A blank cell only shows its background, so the foreground doesn't need to
+    // match, unless underline draws a line in it or reverse fills the cell with it
+    fn matches_style(&self, pixel: &Pixel) -> bool {
+        let style = pixel.get_style();
+
+        match self.style {
+            Some(current) if pixel.char_value == ' ' && !style.underline && !style.reverse => {
+                current.bg == style.bg && effects(&current) == effects(&style)
+            }
+            Some(current) => current == style,
+            None => false,
+        }
+    }
+
+    fn set_style(&mut self, pixel: &Pixel, out: &mut Vec<u8>) -> io::Result<()> {
+        if self.matches_style(pixel) {
+            return Ok(());
+        }
+
+        let style = pixel.get_style();
+
+        // Only send the half of the colour pair that actually changed
+        match self.style {
+            Some(current) if effects(&current) == effects(&style) && current.bg == style.bg => {
+                write!(out, "\x1b[{}m", sgr_colour(style.fg, false))?;
+            }
+            Some(current) if effects(&current) == effects(&style) && current.fg == style.fg => {
+                write!(out, "\x1b[{}m", sgr_colour(style.bg, true))?;
+            }
+            // Effects can only be turned off by resetting everything
+            current => {
+                let reset =
+                    current.is_some_and(|current| effects(&current) != (false, false, false));
+
+                write_sgr(out, &style, reset)?;
+            }
+        }
+
+        self.style = Some(style);
+
+        Ok(())
+    }
+}
+
+/// Writes the SGR sequence that selects a style, after resetting any effects
+/// left on from before when `reset` is set.
+pub fn write_sgr<W: Write>(out: &mut W, style: &Style, reset: bool) -> io::Result<()> {
+    let mut parameters = Vec::new();
+
+    if reset {
+        parameters.push("0".to_string());
+    }
+
+    for (effect, code) in [
+        (style.bold, "1"),
+        (style.underline, "4"),
+        (style.reverse, "7"),
+    ] {
+        if effect {
+            parameters.push(code.to_string());
+        }
+    }
+
+    parameters.push(sgr_colour(style.fg, false));
+    parameters.push(sgr_colour(style.bg, true));
+
+    write!(out, "\x1b[{}m", parameters.join(";"))
+}
+
+fn effects(style: &Style) -> (bool, bool, bool) {
+    (style.bold, style.underline, style.reverse)
+}
+
+// The SGR parameters for a colour; the 16 colours use the classic codes, which
+// every terminal understands
+fn sgr_colour(colour: Colour, background: bool) -> String {
+    let offset = if background { 10 } else { 0 };
+
+    match colour {
+        Colour::Indexed(index) => {
+            let ansi = indexed_to_ansi(index & 0x0F);
+
+            if ansi & 0x08 != 0 {
+                format!("{}", 90 + offset + (ansi & 0x07))
+            } else {
+                format!("{}", 30 + offset + ansi)
+            }
+        }
+        Colour::Xterm(index) => format!("{};5;{}", 38 + offset, index),
+        Colour::Rgb(red, green, blue) => {
+            format!("{};2;{};{};{}", 38 + offset, red, green, blue)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{colour, Console, PIXEL_EMPTY};
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct Capture(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Capture {
+        fn take(&self) -> String {
+            String::from_utf8(self.0.replace(Vec::new())).unwrap()
+        }
+    }
+
+    fn sgr(attributes: u16) -> String {
+        let mut out = Vec::new();
+        let pixel = pixel(' ', attributes);
+        write_sgr(&mut out, &pixel.get_style(), false).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    fn pixel(char_value: char, attributes: u16) -> Pixel {
+        Pixel {
+            char_value,
+            attributes,
+            colours: None,
+        }
+    }
+
+    #[test]
+    fn test_sgr_colours() {
+        assert_eq!(sgr(colour::FG_BLACK | colour::BG_BLACK), "\x1b[30;40m");
+        assert_eq!(
+            sgr(colour::FG_DARK_RED | colour::BG_DARK_BLUE),
+            "\x1b[31;44m"
+        );
+        assert_eq!(sgr(colour::FG_GREY | colour::BG_DARK_YELLOW), "\x1b[37;43m");
+        assert_eq!(sgr(colour::FG_DARK_GREY | colour::BG_WHITE), "\x1b[90;107m");
+        assert_eq!(sgr(colour::FG_CYAN | colour::BG_MAGENTA), "\x1b[96;105m");
+    }
+
+    #[test]
+    fn test_sgr_extended_colours() {
+        let mut out = Vec::new();
+        let style = Style::new(Colour::Rgb(255, 128, 0), Colour::Xterm(17));
+        write_sgr(&mut out, &style, false).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[38;2;255;128;0;48;5;17m"
+        );
+
+        // Only the background changes between the two cells
+        let mut encoder = AnsiEncoder::new();
+        let mut out = Vec::new();
+
+        let frame = [
+            Pixel::new('a', Style::new(Colour::Rgb(1, 2, 3), Colour::BLACK)),
+            Pixel::new('b', Style::new(Colour::Rgb(1, 2, 3), Colour::Rgb(4, 5, 6))),
+        ];
+
+        encoder.encode(&frame, None, 2, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[1H\x1b[38;2;1;2;3;40ma\x1b[48;2;4;5;6mb"
+        );
+    }
+
+    #[test]
+    fn test_encode_effects() {
+        let mut encoder = AnsiEncoder::new();
+        let mut out = Vec::new();
+
+        let bold = Style {
+            bold: true,
+            underline: true,
+            ..Style::fg(Colour::WHITE)
+        };
+        let frame = [
+            Pixel::new('a', bold),
+            Pixel::new('b', bold),
+            Pixel::new('c', Style::fg(Colour::WHITE)),
+        ];
+
+        encoder.encode(&frame, None, 3, &mut out).unwrap();
+
+        // Turning the effects off resets everything, so the colours are sent again
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[1H\x1b[1;4;97;40mab\x1b[0;97;40mc"
+        );
+    }
+
+    #[test]
+    fn test_encode_blank_effects() {
+        let mut encoder = AnsiEncoder::new();
+        let mut out = Vec::new();
+
+        let underline = |fg| Style {
+            underline: true,
+            ..Style::fg(fg)
+        };
+        let frame = [
+            Pixel::new(' ', underline(Colour::WHITE)),
+            Pixel::new(' ', underline(Colour::RED)),
+        ];
+
+        encoder.encode(&frame, None, 2, &mut out).unwrap();
+
+        // The underline is drawn in the foreground, so a blank's still matters
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[1H\x1b[4;97;40m \x1b[91m "
+        );
+    }
+
+    #[test]
+    fn test_encode_full_frame() {
+        let mut encoder = AnsiEncoder::new();
+        let mut out = Vec::new();
+
+        let frame = [
+            pixel('a', colour::FG_WHITE),
+            pixel('b', colour::FG_WHITE),
+            pixel('c', colour::FG_RED),
+            pixel(' ', colour::FG_GREEN),
+        ];
+
+        let cells = encoder.encode(&frame, None, 2, &mut out).unwrap();
+
+        assert_eq!(cells, 4);
+        // The blank cell keeps the red foreground since only its background shows
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[1H\x1b[97;40mab\x1b[2H\x1b[91mc "
+        );
+    }
+
+    #[test]
+    fn test_encode_only_changed_cells() {
+        let mut encoder = AnsiEncoder::new();
+        let mut out = Vec::new();
+
+        let previous = vec![pixel('.', colour::FG_WHITE); 20];
+        encoder.encode(&previous, None, 10, &mut out).unwrap();
+
+        let mut frame = previous.clone();
+        frame[2] = pixel('x', colour::FG_WHITE);
+        frame[4] = pixel('y', colour::FG_WHITE);
+        frame[9] = pixel('z', colour::FG_WHITE | colour::BG_BLUE);
+        frame[13] = pixel('w', colour::FG_WHITE);
+
+        out.clear();
+        let cells = encoder
+            .encode(&frame, Some(&previous), 10, &mut out)
+            .unwrap();
+
+        // The one cell gap between x and y is reprinted, the four cell gap to z is
+        // jumped over, and z changes only the background colour
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "\x1b[1;3Hx.y\x1b[4C\x1b[104mz\x1b[2;4H\x1b[40mw"
+        );
+        assert_eq!(cells, 5);
+
+        out.clear();
+        let cells = encoder.encode(&frame, Some(&frame), 10, &mut out).unwrap();
+
+        assert_eq!(cells, 0);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_encode_wide_characters() {
+        let mut encoder = AnsiEncoder::new();
+        let mut out = Vec::new();
+
+        let previous = vec![pixel('.', colour::FG_WHITE); 6];
+        encoder.encode(&previous, None, 6, &mut out).unwrap();
+
+        let mut frame = previous.clone();
+        frame[0] = pixel('日', colour::FG_WHITE);
+        frame[1] = pixel(WIDE_CONTINUATION, colour::FG_WHITE);
+        frame[4] = pixel('本', colour::FG_WHITE);
+        frame[5] = pixel(WIDE_CONTINUATION, colour::FG_WHITE);
+
+        out.clear();
+        let cells = encoder
+            .encode(&frame, Some(&previous), 6, &mut out)
+            .unwrap();
+
+        // The cursor moves two columns for each wide character, and the gap
+        // between them is reprinted
+        assert_eq!(String::from_utf8(out.clone()).unwrap(), "\x1b[1H日..本");
+        assert_eq!(cells, 6);
+
+        // Drawing over the right half blanks the left half, even though it
+        // hasn't changed itself
+        let mut next = frame.clone();
+        next[1] = pixel('x', colour::FG_WHITE);
+
+        out.clear();
+        encoder.encode(&next, Some(&frame), 6, &mut out).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1b[1H x");
+    }
+
+    #[test]
+    fn test_console_frame_stats() {
+        let capture = Capture::default();
+        let backend = AnsiBackend::with_writer(Box::new(capture.clone()), "TEST").unwrap();
+        let mut console = Console::with_backend(40, 10, Box::new(backend));
+
+        capture.take();
+        console.update_screen().unwrap();
+
+        let full_frame = capture.take();
+        let full_stats = console.get_frame_stats();
+
+        assert_eq!(full_stats.cells_written, 400);
+        assert_eq!(full_stats.bytes_written, full_frame.len());
+
+        console.draw_string(5, 5, "Hi", Style::fg(Colour::WHITE));
+        console.update_screen().unwrap();
+
+        let diff_frame = capture.take();
+        let diff_stats = console.get_frame_stats();
+
+        assert_eq!(diff_frame, "\x1b[6;6H\x1b[97mHi");
+        assert_eq!(diff_stats.cells_written, 2);
+        assert_eq!(diff_stats.bytes_written, diff_frame.len());
+        assert!(diff_stats.bytes_written * 10 < full_stats.bytes_written);
+
+        console.invalidate();
+        console.draw_pixel(5, 5, &PIXEL_EMPTY);
+        console.draw_pixel(6, 5, &PIXEL_EMPTY);
+        console.update_screen().unwrap();
+
+        assert_eq!(console.get_frame_stats().cells_written, 400);
+    }
+}
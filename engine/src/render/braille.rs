@@ -0,0 +1,174 @@
+// Eight dots per console cell, using the braille patterns block (U+2800 to U+28FF).
+use super::{Canvas, Colour, Console, Pixel, Rect, Style};
+
+const BRAILLE_BLANK: u32 = 0x2800;
+
+// The bit for each dot of a cell, by [row][column]
+const DOT_BITS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+#[derive(Copy, Clone)]
+struct BrailleCell {
+    dots: u8,
+    colour: Colour,
+}
+
+const EMPTY_CELL: BrailleCell = BrailleCell {
+    dots: 0,
+    colour: Colour::BLACK,
+};
+
+/// A surface of dots, two across and four down per console cell.
+///
+/// A cell can only show one colour, so it takes the colour of the last dot
+/// plotted in it. Cells without any dots are transparent when drawn with
+/// `draw_to`, and dots keep the background of the cell they are drawn over.
+pub struct BrailleCanvas {
+    width: usize,
+    height: usize,
+    columns: usize,
+    cells: Vec<BrailleCell>,
+}
+
+impl BrailleCanvas {
+    /// Creates a canvas `width` by `height` dots, covering `width / 2` by
+    /// `height / 4` console cells (rounded up).
+    pub fn new(width: usize, height: usize) -> BrailleCanvas {
+        let (columns, rows) = (width.div_ceil(2), height.div_ceil(4));
+
+        BrailleCanvas {
+            width,
+            height,
+            columns,
+            cells: vec![EMPTY_CELL; columns * rows],
+        }
+    }
+
+    /// Removes every dot.
+    pub fn clear(&mut self) {
+        self.cells.fill(EMPTY_CELL);
+    }
+
+    /// Removes the dot at (x, y), if there is one.
+    pub fn unplot(&mut self, x: i32, y: i32) {
+        if let Some((cell, bit)) = self.locate(x, y) {
+            self.cells[cell].dots &= !bit;
+        }
+    }
+
+    pub fn get_dot(&self, x: i32, y: i32) -> bool {
+        match self.locate(x, y) {
+            Some((cell, bit)) => self.cells[cell].dots & bit != 0,
+            None => false,
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// Draws the canvas onto the console with its top left corner in cell (x, y).
+    pub fn draw_to(&self, console: &mut Console, x: i32, y: i32) {
+        for (index, cell) in self.cells.iter().enumerate() {
+            if cell.dots == 0 {
+                continue;
+            }
+
+            let cell_x = x + (index % self.columns) as i32;
+            let cell_y = y + (index / self.columns) as i32;
+
+            let background = console.get_background(cell_x, cell_y);
+            let char_value = char::from_u32(BRAILLE_BLANK + cell.dots as u32).unwrap();
+            let style = Style::new(cell.colour, background);
+
+            console.draw_pixel(cell_x, cell_y, &Pixel::new(char_value, style));
+        }
+    }
+
+    // The index of the cell holding the dot at (x, y) and the dot's bit in it
+    fn locate(&self, x: i32, y: i32) -> Option<(usize, u8)> {
+        if !Rect::from_size(self.width, self.height).contains(x, y) {
+            return None;
+        }
+
+        let (x, y) = (x as usize, y as usize);
+
+        Some(((y / 4) * self.columns + x / 2, DOT_BITS[y % 4][x % 2]))
+    }
+}
+
+impl Canvas for BrailleCanvas {
+    type Ink = Colour;
+
+    fn plot(&mut self, x: i32, y: i32, colour: &Colour) {
+        if let Some((cell, bit)) = self.locate(x, y) {
+            self.cells[cell].dots |= bit;
+            self.cells[cell].colour = *colour;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{HeadlessBackend, Rect, PIXEL_EMPTY};
+
+    #[test]
+    fn test_braille() {
+        let mut console = Console::with_backend(3, 2, Box::new(HeadlessBackend::new()));
+        console.fill(
+            0,
+            0,
+            &Pixel::new(' ', Style::new(Colour::BLACK, Colour::DARK_BLUE)),
+        );
+
+        // A 5x5 canvas covers 3x2 cells
+        let mut canvas = BrailleCanvas::new(5, 5);
+        canvas.draw_line(0, 0, 1, 3, &Colour::GREEN);
+        canvas.plot(4, 4, &Colour::RED);
+        canvas.plot(5, 0, &Colour::RED);
+
+        assert!(canvas.get_dot(1, 3));
+        assert!(!canvas.get_dot(1, 0));
+
+        canvas.draw_to(&mut console, 0, 0);
+
+        // The line covers dots 1, 2, 6 and 8
+        assert_eq!(
+            console.get_pixel(0, 0),
+            Pixel::new('⢣', Style::new(Colour::GREEN, Colour::DARK_BLUE))
+        );
+        assert_eq!(
+            console.get_pixel(2, 1),
+            Pixel::new('⠁', Style::new(Colour::RED, Colour::DARK_BLUE))
+        );
+        assert_eq!(console.get_pixel(1, 0).char_value, ' ');
+
+        // The clip is respected, and a cell with no dots left is transparent again
+        canvas.unplot(4, 4);
+        console.fill(0, 0, &PIXEL_EMPTY);
+        console.push_clip(Rect::new(1, 0, 2, 2));
+        canvas.draw_to(&mut console, 0, 0);
+
+        assert_eq!(console.screen_buffer, vec![PIXEL_EMPTY; 6]);
+
+        // Dots keep backgrounds beyond the 16 colours too
+        let sky = Colour::Rgb(80, 160, 255);
+        console.pop_clip();
+        console.fill(0, 0, &Pixel::new(' ', Style::new(Colour::WHITE, sky)));
+        canvas.draw_to(&mut console, 0, 0);
+        assert_eq!(console.get_pixel(0, 0).char_value, '⢣');
+        assert_eq!(console.get_pixel(0, 0).get_bg(), sky);
+
+        // Cells off the top left of the screen are skipped
+        let before = console.screen_buffer.clone();
+        canvas.draw_to(&mut console, -1, -1);
+        assert_eq!(console.screen_buffer, before);
+
+        canvas.clear();
+        assert!(!canvas.get_dot(0, 0));
+    }
+}
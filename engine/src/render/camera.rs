@@ -0,0 +1,285 @@
+// World-space drawing for games whose world is bigger than the screen, or just
+// not lined up with it.
+use super::{Canvas, Console, MarkupError, Pixel, Rect, Sprite, Style};
+
+/// Looks at part of the world and shows it in a viewport, an area of the screen.
+///
+/// The camera's position is the world point shown in the top left corner of
+/// the viewport. It can follow a target, easing towards it, and can be kept
+/// inside the bounds of the world.
+pub struct Camera {
+    x: f64,
+    y: f64,
+    viewport: Rect,
+    target: Option<(f64, f64)>,
+    smoothing: f64,
+    bounds: Option<Rect>,
+}
+
+impl Camera {
+    /// Creates a camera at the world origin, shown in `viewport`.
+    pub fn new(viewport: Rect) -> Camera {
+        Camera {
+            x: 0.0,
+            y: 0.0,
+            viewport,
+            target: None,
+            smoothing: 0.0,
+            bounds: None,
+        }
+    }
+
+    pub fn set_position(&mut self, x: f64, y: f64) {
+        self.x = x;
+        self.y = y;
+        self.clamp_to_bounds();
+    }
+
+    pub fn get_position(&self) -> (f64, f64) {
+        (self.x, self.y)
+    }
+
+    pub fn set_viewport(&mut self, viewport: Rect) {
+        self.viewport = viewport;
+        self.clamp_to_bounds();
+    }
+
+    pub fn get_viewport(&self) -> Rect {
+        self.viewport
+    }
+
+    /// Keeps the world point (x, y) in the centre of the viewport from the next
+    /// `update` on; call it every frame with a moving target's position.
+    pub fn follow(&mut self, x: f64, y: f64) {
+        self.target = Some((x, y));
+    }
+
+    pub fn stop_following(&mut self) {
+        self.target = None;
+    }
+
+    /// How quickly the camera catches up with its target: 0 snaps straight to
+    /// it, and higher values close more of the distance each second.
+    pub fn set_smoothing(&mut self, smoothing: f64) {
+        self.smoothing = smoothing.max(0.0);
+    }
+
+    /// Keeps the viewport inside this area of the world, or lifts the limit.
+    pub fn set_bounds(&mut self, bounds: Option<Rect>) {
+        self.bounds = bounds;
+        self.clamp_to_bounds();
+    }
+
+    /// Moves the camera towards its target, if it has one.
+    pub fn update(&mut self, delta: f64) {
+        let (target_x, target_y) = match self.target {
+            Some(target) => target,
+            None => return,
+        };
+
+        let goal_x = target_x - self.viewport.width as f64 / 2.0;
+        let goal_y = target_y - self.viewport.height as f64 / 2.0;
+
+        // Exponential easing, so the result doesn't depend on the frame rate
+        let amount = if self.smoothing == 0.0 {
+            1.0
+        } else {
+            1.0 - (-self.smoothing * delta).exp()
+        };
+
+        self.x += (goal_x - self.x) * amount;
+        self.y += (goal_y - self.y) * amount;
+        self.clamp_to_bounds();
+    }
+
+    pub fn world_to_screen(&self, x: i32, y: i32) -> (i32, i32) {
+        let (offset_x, offset_y) = self.offset();
+
+        (x + offset_x, y + offset_y)
+    }
+
+    pub fn screen_to_world(&self, x: i32, y: i32) -> (i32, i32) {
+        let (offset_x, offset_y) = self.offset();
+
+        (x - offset_x, y - offset_y)
+    }
+
+    /// Starts drawing through the camera; everything drawn with the Viewport is
+    /// placed by world position and clipped to the viewport.
+    pub fn view<'a>(&self, console: &'a mut Console) -> Viewport<'a> {
+        let (offset_x, offset_y) = self.offset();
+
+        console.push_clip(self.viewport);
+
+        Viewport {
+            console,
+            offset_x,
+            offset_y,
+        }
+    }
+
+    // What to add to world positions to get screen positions
+    fn offset(&self) -> (i32, i32) {
+        (
+            self.viewport.x - self.x.floor() as i32,
+            self.viewport.y - self.y.floor() as i32,
+        )
+    }
+
+    fn clamp_to_bounds(&mut self) {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        // A world smaller than the viewport stays in its top left corner
+        let max_x = (bounds.x + bounds.width - self.viewport.width).max(bounds.x);
+        let max_y = (bounds.y + bounds.height - self.viewport.height).max(bounds.y);
+
+        self.x = self.x.clamp(bounds.x as f64, max_x as f64);
+        self.y = self.y.clamp(bounds.y as f64, max_y as f64);
+    }
+}
+
+/// Draws onto a Console in world coordinates; made by `Camera::view`, and
+/// puts the Console's clip back as it was when dropped.
+pub struct Viewport<'a> {
+    console: &'a mut Console,
+    offset_x: i32,
+    offset_y: i32,
+}
+
+impl Viewport<'_> {
+    pub fn draw_pixel(&mut self, x: i32, y: i32, pixel: &Pixel) {
+        self.console
+            .draw_pixel(x + self.offset_x, y + self.offset_y, pixel);
+    }
+
+    pub fn draw_string(&mut self, x: i32, y: i32, string: &str, style: Style) {
+        self.console
+            .draw_string(x + self.offset_x, y + self.offset_y, string, style);
+    }
+
+    pub fn draw_markup(
+        &mut self,
+        x: i32,
+        y: i32,
+        markup: &str,
+        style: Style,
+    ) -> Result<(), MarkupError> {
+        self.console
+            .draw_markup(x + self.offset_x, y + self.offset_y, markup, style)
+    }
+
+    pub fn draw_sprite(&mut self, x: i32, y: i32, sprite: &Sprite) {
+        self.console
+            .draw_sprite(x + self.offset_x, y + self.offset_y, sprite);
+    }
+
+    pub fn draw_partial_sprite(&mut self, x: i32, y: i32, sprite: &Sprite, source: Rect) {
+        self.console
+            .draw_partial_sprite(x + self.offset_x, y + self.offset_y, sprite, source);
+    }
+
+    /// Fills the whole viewport.
+    pub fn clear(&mut self, pixel: &Pixel) {
+        let clip = self.console.get_clip();
+
+        self.console
+            .fill_rect(clip.x, clip.y, clip.width, clip.height, pixel);
+    }
+}
+
+impl Canvas for Viewport<'_> {
+    type Ink = Pixel;
+
+    fn plot(&mut self, x: i32, y: i32, pixel: &Pixel) {
+        self.draw_pixel(x, y, pixel);
+    }
+}
+
+impl Drop for Viewport<'_> {
+    fn drop(&mut self) {
+        self.console.pop_clip();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{colour, HeadlessBackend, PIXEL, PIXEL_EMPTY};
+
+    const PIXEL_WHITE: Pixel = Pixel {
+        char_value: PIXEL,
+        attributes: colour::FG_WHITE,
+        colours: None,
+    };
+
+    #[test]
+    fn test_viewport() {
+        let mut console = Console::with_backend(6, 4, Box::new(HeadlessBackend::new()));
+        let mut camera = Camera::new(Rect::new(1, 1, 3, 2));
+        camera.set_position(10.0, 20.0);
+
+        assert_eq!(camera.world_to_screen(11, 21), (2, 2));
+        assert_eq!(camera.screen_to_world(2, 2), (11, 21));
+
+        {
+            let mut view = camera.view(&mut console);
+
+            view.draw_pixel(10, 20, &PIXEL_WHITE);
+            // Off the edge of the viewport, though still on the console
+            view.draw_pixel(13, 20, &PIXEL_WHITE);
+            view.draw_line(9, 21, 20, 21, &PIXEL_WHITE);
+        }
+
+        // The clip is back to the whole console
+        assert_eq!(console.get_clip(), Rect::new(0, 0, 6, 4));
+
+        for y in 0..4 {
+            for x in 0..6 {
+                let expected = if (x, y) == (1, 1) || (y == 2 && (1..4).contains(&x)) {
+                    PIXEL_WHITE
+                } else {
+                    PIXEL_EMPTY
+                };
+
+                assert_eq!(console.get_pixel(x, y), expected, "at {}, {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_follow() {
+        let mut camera = Camera::new(Rect::new(0, 0, 10, 4));
+
+        camera.follow(20.0, 10.0);
+        camera.update(0.1);
+
+        assert_eq!(camera.get_position(), (15.0, 8.0));
+
+        // Easing closes the same share of the distance per second, whatever
+        // the frame rate
+        let mut smooth = Camera::new(Rect::new(0, 0, 10, 4));
+        smooth.set_smoothing(2.0_f64.ln());
+        smooth.follow(25.0, 2.0);
+
+        for _ in 0..4 {
+            smooth.update(0.25);
+        }
+
+        let (x, y) = smooth.get_position();
+        assert!((x - 10.0).abs() < 1e-9);
+        assert!(y.abs() < 1e-9);
+
+        // Bounds keep the viewport inside the world
+        camera.set_bounds(Some(Rect::new(0, 0, 12, 30)));
+
+        assert_eq!(camera.get_position(), (2.0, 8.0));
+
+        camera.follow(0.0, 0.0);
+        camera.update(0.1);
+
+        assert_eq!(camera.get_position(), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,281 @@
+// Shape drawing shared by everything that can be drawn onto, à la olcConsoleGameEngine.
+
+/// A surface the shape primitives can draw onto.
+///
+/// Only `plot` has to be implemented; it should quietly skip points outside the
+/// surface, which lets shapes hang off the edges.
+pub trait Canvas {
+    /// What a point is drawn with, e.g. a Pixel for a Console.
+    type Ink;
+
+    fn plot(&mut self, x: i32, y: i32, ink: &Self::Ink);
+
+    /// Draws a line from (x0, y0) to (x1, y1), both ends included.
+    fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, ink: &Self::Ink) {
+        // Bresenham's algorithm, covering every octant
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y) = (x0, y0);
+        let mut error = dx + dy;
+
+        loop {
+            self.plot(x, y, ink);
+
+            if x == x1 && y == y1 {
+                break;
+            }
+
+            let error2 = error * 2;
+
+            if error2 >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if error2 <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Draws the outline of a `width` by `height` rectangle.
+    fn draw_rect(&mut self, x: i32, y: i32, width: i32, height: i32, ink: &Self::Ink) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        let (right, bottom) = (x + width - 1, y + height - 1);
+
+        draw_span(self, x, right, y, ink);
+        draw_span(self, x, right, bottom, ink);
+
+        for side_y in y + 1..bottom {
+            self.plot(x, side_y, ink);
+            self.plot(right, side_y, ink);
+        }
+    }
+
+    fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, ink: &Self::Ink) {
+        for row in y..y + height.max(0) {
+            draw_span(self, x, x + width - 1, row, ink);
+        }
+    }
+
+    fn draw_circle(&mut self, centre_x: i32, centre_y: i32, radius: i32, ink: &Self::Ink) {
+        for_each_octant_point(radius, |x, y| {
+            for (point_x, point_y) in [
+                (x, y),
+                (y, x),
+                (-x, y),
+                (-y, x),
+                (x, -y),
+                (y, -x),
+                (-x, -y),
+                (-y, -x),
+            ] {
+                self.plot(centre_x + point_x, centre_y + point_y, ink);
+            }
+        });
+    }
+
+    fn fill_circle(&mut self, centre_x: i32, centre_y: i32, radius: i32, ink: &Self::Ink) {
+        for_each_octant_point(radius, |x, y| {
+            draw_span(self, centre_x - x, centre_x + x, centre_y + y, ink);
+            draw_span(self, centre_x - x, centre_x + x, centre_y - y, ink);
+            draw_span(self, centre_x - y, centre_x + y, centre_y + x, ink);
+            draw_span(self, centre_x - y, centre_x + y, centre_y - x, ink);
+        });
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_triangle(
+        &mut self,
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        ink: &Self::Ink,
+    ) {
+        self.draw_line(x0, y0, x1, y1, ink);
+        self.draw_line(x1, y1, x2, y2, ink);
+        self.draw_line(x2, y2, x0, y0, ink);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn fill_triangle(
+        &mut self,
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        ink: &Self::Ink,
+    ) {
+        let mut points = [(x0, y0), (x1, y1), (x2, y2)];
+        points.sort_by_key(|&(_, y)| y);
+
+        let [(top_x, top_y), (middle_x, middle_y), (bottom_x, bottom_y)] = points;
+
+        // Fill one row at a time between the long edge (top to bottom) and
+        // whichever of the two short edges covers that row
+        for y in top_y..=bottom_y {
+            let long_x = edge_x(top_x, top_y, bottom_x, bottom_y, y);
+            let short_x = if y < middle_y {
+                edge_x(top_x, top_y, middle_x, middle_y, y)
+            } else {
+                edge_x(middle_x, middle_y, bottom_x, bottom_y, y)
+            };
+
+            draw_span(self, long_x.min(short_x), long_x.max(short_x), y, ink);
+        }
+
+        // The rounding along the edges can leave gaps next to the outline
+        self.draw_triangle(x0, y0, x1, y1, x2, y2, ink);
+    }
+}
+
+// Plots every point from x0 to x1 on row y, both ends included.
+fn draw_span<C: Canvas + ?Sized>(canvas: &mut C, x0: i32, x1: i32, y: i32, ink: &C::Ink) {
+    for x in x0..=x1 {
+        canvas.plot(x, y, ink);
+    }
+}
+
+// Where the edge from (x0, y0) to (x1, y1) crosses row y.
+fn edge_x(x0: i32, y0: i32, x1: i32, y1: i32, y: i32) -> i32 {
+    if y0 == y1 {
+        return x0;
+    }
+
+    x0 + (x1 - x0) * (y - y0) / (y1 - y0)
+}
+
+// Midpoint circle algorithm; calls `plot` with each point of the octant between
+// 12 o'clock and half past one, relative to the centre.
+fn for_each_octant_point<F: FnMut(i32, i32)>(radius: i32, mut plot: F) {
+    if radius < 0 {
+        return;
+    }
+
+    let (mut x, mut y) = (0, radius);
+    let mut decision = 3 - 2 * radius;
+
+    while x <= y {
+        plot(x, y);
+
+        if decision < 0 {
+            decision += 4 * x + 6;
+        } else {
+            decision += 4 * (x - y) + 10;
+            y -= 1;
+        }
+
+        x += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Records which points were plotted as rows of '#' and '.'
+    struct Grid {
+        width: i32,
+        cells: Vec<bool>,
+    }
+
+    impl Grid {
+        fn new(width: i32, height: i32) -> Grid {
+            Grid {
+                width,
+                cells: vec![false; (width * height) as usize],
+            }
+        }
+
+        fn rows(&self) -> Vec<String> {
+            self.cells
+                .chunks(self.width as usize)
+                .map(|row| row.iter().map(|&set| if set { '#' } else { '.' }).collect())
+                .collect()
+        }
+    }
+
+    impl Canvas for Grid {
+        type Ink = ();
+
+        fn plot(&mut self, x: i32, y: i32, _ink: &()) {
+            let height = self.cells.len() as i32 / self.width;
+
+            if x >= 0 && y >= 0 && x < self.width && y < height {
+                self.cells[(y * self.width + x) as usize] = true;
+            }
+        }
+    }
+
+    #[test]
+    fn test_draw_line() {
+        let mut grid = Grid::new(6, 4);
+        grid.draw_line(0, 0, 5, 3, &());
+
+        assert_eq!(grid.rows(), ["#.....", ".##...", "...##.", ".....#"]);
+
+        // Drawn backwards, the line covers the same kind of path
+        let mut grid = Grid::new(6, 4);
+        grid.draw_line(5, 0, 0, 0, &());
+        grid.draw_line(2, 3, 2, 1, &());
+
+        assert_eq!(grid.rows(), ["######", "..#...", "..#...", "..#..."]);
+    }
+
+    #[test]
+    fn test_rects() {
+        let mut grid = Grid::new(6, 5);
+        grid.draw_rect(1, 1, 4, 3, &());
+
+        assert_eq!(
+            grid.rows(),
+            ["......", ".####.", ".#..#.", ".####.", "......"]
+        );
+
+        let mut grid = Grid::new(4, 3);
+        grid.fill_rect(-2, 1, 4, 5, &());
+        grid.fill_rect(3, 0, 0, 3, &());
+
+        assert_eq!(grid.rows(), ["....", "##..", "##.."]);
+    }
+
+    #[test]
+    fn test_circles() {
+        let mut grid = Grid::new(7, 7);
+        grid.draw_circle(3, 3, 3, &());
+
+        assert_eq!(
+            grid.rows(),
+            ["..###..", ".#...#.", "#.....#", "#.....#", "#.....#", ".#...#.", "..###.."]
+        );
+
+        let mut grid = Grid::new(7, 7);
+        grid.fill_circle(3, 3, 3, &());
+
+        assert_eq!(
+            grid.rows(),
+            ["..###..", ".#####.", "#######", "#######", "#######", ".#####.", "..###.."]
+        );
+    }
+
+    #[test]
+    fn test_triangles() {
+        let mut grid = Grid::new(5, 5);
+        grid.draw_triangle(0, 0, 4, 4, 0, 4, &());
+
+        assert_eq!(grid.rows(), ["#....", "##...", "#.#..", "#..#.", "#####"]);
+
+        let mut grid = Grid::new(5, 5);
+        grid.fill_triangle(0, 4, 0, 0, 4, 4, &());
+
+        assert_eq!(grid.rows(), ["#....", "##...", "###..", "####.", "#####"]);
+    }
+}
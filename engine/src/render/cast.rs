@@ -0,0 +1,217 @@
+// Session recordings in asciinema's v2 .cast format: a JSON header line, then
+// one JSON line per presented frame with the escape sequences that draw it.
+use super::ansi::AnsiEncoder;
+use super::Pixel;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// Hide the cursor and clear the player's screen before the first frame
+const START: &str = "\x1b[?25l\x1b[2J";
+// Reset colours and show the cursor again once the recording ends
+const END: &str = "\x1b[0m\x1b[?25h";
+
+/// Writes what a Console presents to a .cast file, so sessions can be played
+/// back with asciinema or any other cast player; see `Console::start_recording`.
+///
+/// Frames are encoded the same way AnsiBackend draws them, whatever backend
+/// the Console presents to, so headless sessions can be recorded too.
+pub struct CastRecorder {
+    out: Box<dyn Write>,
+    encoder: AnsiEncoder,
+    buffer: Vec<u8>,
+    start: Instant,
+    started: bool,
+}
+
+impl CastRecorder {
+    /// Creates (or replaces) the .cast file at `path` for a width by height
+    /// console.
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        width: u16,
+        height: u16,
+        title: &str,
+    ) -> io::Result<CastRecorder> {
+        let out = BufWriter::new(File::create(path)?);
+
+        CastRecorder::with_writer(Box::new(out), width, height, title)
+    }
+
+    /// Like `create`, but writes the recording to `out`.
+    pub fn with_writer(
+        mut out: Box<dyn Write>,
+        width: u16,
+        height: u16,
+        title: &str,
+    ) -> io::Result<CastRecorder> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        writeln!(
+            out,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}, \"title\": {}}}",
+            width,
+            height,
+            timestamp,
+            json_string(title)
+        )?;
+        out.flush()?;
+
+        Ok(CastRecorder {
+            out,
+            encoder: AnsiEncoder::new(),
+            buffer: Vec::new(),
+            start: Instant::now(),
+            started: false,
+        })
+    }
+
+    // Records the cells of `frame` that differ from `previous`, timed from when
+    // the recorder was created; frames with no changes aren't recorded
+    pub(crate) fn record(
+        &mut self,
+        frame: &[Pixel],
+        previous: Option<&[Pixel]>,
+        width: usize,
+    ) -> io::Result<()> {
+        self.buffer.clear();
+
+        // The first frame is drawn in full, whatever was presented before it
+        let previous = if self.started {
+            previous
+        } else {
+            self.buffer.extend_from_slice(START.as_bytes());
+            self.started = true;
+
+            None
+        };
+
+        self.encoder
+            .encode(frame, previous, width, &mut self.buffer)?;
+
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let output = String::from_utf8_lossy(&self.buffer).into_owned();
+
+        self.write_event(&output)
+    }
+
+    // Each event is flushed straight away, so a session that is killed still
+    // leaves a recording that plays up to that point
+    fn write_event(&mut self, output: &str) -> io::Result<()> {
+        let time = self.start.elapsed().as_secs_f64();
+
+        writeln!(self.out, "[{:.6}, \"o\", {}]", time, json_string(output))?;
+        self.out.flush()
+    }
+}
+
+impl Drop for CastRecorder {
+    fn drop(&mut self) {
+        if self.started {
+            let _ = self.write_event(END);
+        }
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut json = String::with_capacity(string.len() + 2);
+
+    json.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Colour, Console, HeadlessBackend, Style};
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct Capture(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Capture {
+        fn lines(&self) -> Vec<String> {
+            let text = String::from_utf8(self.0.borrow().clone()).unwrap();
+
+            text.lines().map(str::to_string).collect()
+        }
+    }
+
+    // The output of an event line, leaving out its time
+    fn output(line: &str) -> &str {
+        let start = line.find(", \"o\", ").unwrap() + 7;
+
+        &line[start..line.len() - 1]
+    }
+
+    #[test]
+    fn test_record() {
+        let capture = Capture::default();
+        let recorder = CastRecorder::with_writer(Box::new(capture.clone()), 4, 2, "A \"test\"");
+        let mut console = Console::with_backend(4, 2, Box::new(HeadlessBackend::new()));
+
+        console.start_recording(recorder.unwrap());
+        console.draw_string(0, 0, "ab", Style::fg(Colour::WHITE));
+        console.update_screen().unwrap();
+        console.update_screen().unwrap();
+        console.draw_string(1, 1, "\\", Style::fg(Colour::RED));
+        console.update_screen().unwrap();
+        console.stop_recording();
+
+        let lines = capture.lines();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("{\"version\": 2, \"width\": 4, \"height\": 2, "));
+        assert!(lines[0].ends_with("\"title\": \"A \\\"test\\\"\"}"));
+
+        // The first frame clears the screen and draws every cell; the second
+        // didn't change, so only the third's difference follows it
+        assert!(lines[1].starts_with('['));
+        assert_eq!(
+            output(&lines[1]),
+            "\"\\u001b[?25l\\u001b[2J\\u001b[1H\\u001b[97;40mab  \\u001b[2H    \""
+        );
+        assert_eq!(output(&lines[2]), "\"\\u001b[2;2H\\u001b[91m\\\\\"");
+        assert_eq!(output(&lines[3]), "\"\\u001b[0m\\u001b[?25h\"");
+
+        let times: Vec<f64> = lines[1..]
+            .iter()
+            .map(|line| line[1..line.find(',').unwrap()].parse().unwrap())
+            .collect();
+
+        assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
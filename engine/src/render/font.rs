@@ -0,0 +1,188 @@
+// A 5x7 bitmap font for drawing console cells into images, with the block,
+// shade and braille glyphs the engine draws with.
+use super::{
+    HALF_BLOCK_LOWER, HALF_BLOCK_UPPER, PIXEL, PIXEL_HALF, PIXEL_QUARTER, PIXEL_THREE_QUARTERS,
+};
+
+/// The size in pixels of one console cell drawn with the font.
+pub(crate) const CELL_WIDTH: usize = 6;
+pub(crate) const CELL_HEIGHT: usize = 8;
+
+const BRAILLE_BLANK: u32 = 0x2800;
+
+// The printable ASCII characters from ' ' to '~', five columns each with the
+// top row in the lowest bit; the eighth row is for descenders
+const ASCII: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x56, 0x20, 0x50], // &
+    [0x00, 0x00, 0x07, 0x00, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x2A, 0x1C, 0x7F, 0x1C, 0x2A], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x80, 0x60, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x62, 0x51, 0x49, 0x49, 0x46], // 2
+    [0x22, 0x41, 0x49, 0x49, 0x36], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x80, 0x76, 0x36, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x3E, 0x41, 0x5D, 0x55, 0x1E], // @
+    [0x7E, 0x09, 0x09, 0x09, 0x7E], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x26, 0x49, 0x49, 0x49, 0x32], // S
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x80, 0x80, 0x80, 0x80, 0x80], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // f
+    [0x18, 0xA4, 0xA4, 0xA4, 0x7C], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x40, 0x80, 0x84, 0x7D, 0x00], // j
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0xFC, 0x24, 0x24, 0x24, 0x18], // p
+    [0x18, 0x24, 0x24, 0x24, 0xFC], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x1C, 0xA0, 0xA0, 0xA0, 0x7C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x08, 0x04, 0x08, 0x10, 0x08], // ~
+];
+
+// Whether the pixel at (x, y) of a cell showing `c` is drawn in the foreground
+// colour; `columns` is how many cells wide the character is. Characters the
+// font doesn't have are drawn as an empty box.
+pub(crate) fn is_lit(c: char, columns: usize, x: usize, y: usize) -> bool {
+    match c {
+        PIXEL => true,
+        PIXEL_THREE_QUARTERS => x.is_multiple_of(2) || y.is_multiple_of(2),
+        PIXEL_HALF => (x + y).is_multiple_of(2),
+        PIXEL_QUARTER => x.is_multiple_of(2) && y.is_multiple_of(2),
+        HALF_BLOCK_UPPER => y < CELL_HEIGHT / 2,
+        HALF_BLOCK_LOWER => y >= CELL_HEIGHT / 2,
+        ' '..='~' => {
+            let column = ASCII[c as usize - ' ' as usize].get(x).copied();
+
+            column.is_some_and(|column| column >> y & 1 != 0)
+        }
+        '…' => y == 6 && x.is_multiple_of(2),
+        c if (BRAILLE_BLANK..BRAILLE_BLANK + 0x100).contains(&(c as u32)) => {
+            // A one pixel dot in the middle of each 3x2 block of the cell
+            let dots = c as u32 - BRAILLE_BLANK;
+            let (column, row) = (x / 3, y / 2);
+            let bit = match (column, row) {
+                (0, 3) => 6,
+                (1, 3) => 7,
+                (column, row) => column * 3 + row,
+            };
+
+            x % 3 == 1 && y.is_multiple_of(2) && dots >> bit & 1 != 0
+        }
+        _ => {
+            let right = columns * CELL_WIDTH - 2;
+
+            x <= right && y <= 6 && (x == 0 || x == right || y == 0 || y == 6)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(c: char) -> Vec<String> {
+        (0..CELL_HEIGHT)
+            .map(|y| {
+                (0..CELL_WIDTH)
+                    .map(|x| if is_lit(c, 1, x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_glyphs() {
+        assert_eq!(
+            draw('A'),
+            [".###..", "#...#.", "#...#.", "#####.", "#...#.", "#...#.", "#...#.", "......",]
+        );
+        assert_eq!(
+            draw('g'),
+            ["......", "......", ".####.", "#...#.", "#...#.", ".####.", "....#.", ".###..",]
+        );
+        assert_eq!(draw(' '), vec!["......"; CELL_HEIGHT]);
+        assert_eq!(draw(PIXEL), vec!["######"; CELL_HEIGHT]);
+
+        // The top left and bottom right dots
+        assert_eq!(
+            draw('\u{2881}'),
+            [".#....", "......", "......", "......", "......", "......", "....#.", "......",]
+        );
+
+        // Unknown characters are boxes, as wide as the character
+        assert_eq!(draw('€')[0], "#####.");
+        assert!(is_lit('日', 2, 10, 3));
+        assert!(!is_lit('日', 2, 11, 3));
+    }
+}
@@ -0,0 +1,179 @@
+// Two square-ish pixels per console cell, using the upper and lower half block glyphs.
+use super::{Canvas, Colour, Console, Pixel, Rect, Style, PIXEL};
+
+pub const HALF_BLOCK_UPPER: char = '▀';
+pub const HALF_BLOCK_LOWER: char = '▄';
+
+/// A surface twice as tall as the console cells it covers.
+///
+/// Each cell shows the pixel above in its foreground and the one below in its
+/// background. Pixels start out transparent; `draw_to` leaves the console's
+/// background showing wherever nothing was drawn.
+pub struct HalfBlockCanvas {
+    width: usize,
+    height: usize,
+    // None where nothing has been drawn
+    pixels: Vec<Option<Colour>>,
+}
+
+impl HalfBlockCanvas {
+    /// Creates a canvas `width` pixels wide and `height` pixels tall, which
+    /// covers `height / 2` console rows (rounded up).
+    pub fn new(width: usize, height: usize) -> HalfBlockCanvas {
+        HalfBlockCanvas {
+            width,
+            height,
+            pixels: vec![None; width * height],
+        }
+    }
+
+    /// Makes every pixel transparent again.
+    pub fn clear(&mut self) {
+        self.pixels.fill(None);
+    }
+
+    /// Returns the colour at (x, y), or None if it is transparent or outside the canvas.
+    pub fn get_colour(&self, x: usize, y: usize) -> Option<Colour> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// Draws the canvas onto the console with its top left corner in cell (x, y).
+    pub fn draw_to(&self, console: &mut Console, x: i32, y: i32) {
+        for row in 0..self.height.div_ceil(2) {
+            for column in 0..self.width {
+                let top = self.get_colour(column, row * 2);
+                let bottom = self.get_colour(column, row * 2 + 1);
+
+                let (cell_x, cell_y) = (x + column as i32, y + row as i32);
+
+                let pixel = match (top, bottom) {
+                    (None, None) => continue,
+                    (Some(top), Some(bottom)) if top == bottom => Pixel::new(PIXEL, Style::fg(top)),
+                    (Some(top), Some(bottom)) => {
+                        Pixel::new(HALF_BLOCK_UPPER, Style::new(top, bottom))
+                    }
+                    // Let the background already in the cell show through
+                    // the transparent half, whatever colour it was drawn in
+                    (Some(top), None) => Pixel::new(
+                        HALF_BLOCK_UPPER,
+                        Style::new(top, console.get_background(cell_x, cell_y)),
+                    ),
+                    (None, Some(bottom)) => Pixel::new(
+                        HALF_BLOCK_LOWER,
+                        Style::new(bottom, console.get_background(cell_x, cell_y)),
+                    ),
+                };
+
+                console.draw_pixel(cell_x, cell_y, &pixel);
+            }
+        }
+    }
+}
+
+impl Canvas for HalfBlockCanvas {
+    type Ink = Colour;
+
+    fn plot(&mut self, x: i32, y: i32, colour: &Colour) {
+        if !Rect::from_size(self.width, self.height).contains(x, y) {
+            return;
+        }
+
+        self.pixels[y as usize * self.width + x as usize] = Some(*colour);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{HeadlessBackend, PIXEL_EMPTY};
+
+    #[test]
+    fn test_half_blocks() {
+        let mut console = Console::with_backend(3, 2, Box::new(HeadlessBackend::new()));
+        let backdrop = Pixel::new('.', Style::new(Colour::GREY, Colour::DARK_BLUE));
+        console.fill(0, 0, &backdrop);
+
+        let mut canvas = HalfBlockCanvas::new(3, 3);
+        canvas.draw_line(0, 0, 2, 0, &Colour::RED);
+        canvas.plot(0, 1, &Colour::RED);
+        canvas.plot(1, 1, &Colour::GREEN);
+        canvas.plot(1, 2, &Colour::WHITE);
+
+        assert_eq!(canvas.get_colour(1, 1), Some(Colour::GREEN));
+        assert_eq!(canvas.get_colour(2, 1), None);
+
+        canvas.draw_to(&mut console, 0, 0);
+
+        assert_eq!(
+            console.get_pixel(0, 0),
+            Pixel::new(PIXEL, Style::fg(Colour::RED))
+        );
+        assert_eq!(
+            console.get_pixel(1, 0),
+            Pixel::new(HALF_BLOCK_UPPER, Style::new(Colour::RED, Colour::GREEN))
+        );
+        assert_eq!(
+            console.get_pixel(2, 0),
+            Pixel::new(HALF_BLOCK_UPPER, Style::new(Colour::RED, Colour::DARK_BLUE))
+        );
+
+        // The odd last row only has top halves
+        assert_eq!(console.get_pixel(0, 1), backdrop);
+        assert_eq!(
+            console.get_pixel(1, 1),
+            Pixel::new(
+                HALF_BLOCK_UPPER,
+                Style::new(Colour::WHITE, Colour::DARK_BLUE)
+            )
+        );
+
+        // Off the console is clipped, and a cleared canvas draws nothing
+        canvas.draw_to(&mut console, 2, 1);
+        assert_eq!(console.get_pixel(2, 1).char_value, PIXEL);
+
+        canvas.clear();
+        console.fill(0, 0, &PIXEL_EMPTY);
+        canvas.draw_to(&mut console, 0, 0);
+        assert_eq!(console.screen_buffer, vec![PIXEL_EMPTY; 6]);
+
+        // Lower halves take the background of whatever they're drawn over
+        canvas.plot(0, 1, &Colour::YELLOW);
+        console.fill(0, 0, &backdrop);
+        canvas.draw_to(&mut console, 0, 0);
+        assert_eq!(
+            console.get_pixel(0, 0),
+            Pixel::new(
+                HALF_BLOCK_LOWER,
+                Style::new(Colour::YELLOW, Colour::DARK_BLUE)
+            )
+        );
+
+        // Including backgrounds beyond the 16 colours
+        let sky = Colour::Rgb(80, 160, 255);
+        console.fill(0, 0, &Pixel::new(' ', Style::new(Colour::WHITE, sky)));
+        canvas.draw_to(&mut console, 0, 0);
+        assert_eq!(console.get_pixel(0, 0).get_bg(), sky);
+        assert_eq!(console.get_pixel(0, 0).get_fg(), Colour::YELLOW);
+
+        // And inks beyond them
+        let orange = Colour::Rgb(255, 140, 0);
+        canvas.plot(0, 0, &orange);
+        canvas.draw_to(&mut console, 0, 0);
+        assert_eq!(
+            console.get_pixel(0, 0),
+            Pixel::new(HALF_BLOCK_UPPER, Style::new(orange, Colour::YELLOW))
+        );
+    }
+}
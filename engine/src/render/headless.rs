@@ -0,0 +1,78 @@
+// In-memory backend for tests and for running games without a terminal.
+use super::{ColourDepth, FrameStats, Pixel, RenderBackend};
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+
+/// Records every presented frame instead of drawing it.
+///
+/// Clones share the same recording, so keep a clone around to inspect the
+/// frames after handing the backend to a Console.
+#[derive(Clone)]
+pub struct HeadlessBackend {
+    frames: Rc<RefCell<Vec<Vec<Pixel>>>>,
+    colour_depth: ColourDepth,
+}
+
+impl HeadlessBackend {
+    pub fn new() -> HeadlessBackend {
+        HeadlessBackend {
+            frames: Rc::default(),
+            colour_depth: ColourDepth::TrueColour,
+        }
+    }
+
+    /// Records frames fitted to fewer colours, as a terminal backend would
+    /// present them; true colour to begin with.
+    pub fn set_colour_depth(&mut self, colour_depth: ColourDepth) {
+        self.colour_depth = colour_depth;
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.borrow().len()
+    }
+
+    pub fn frame(&self, index: usize) -> Option<Vec<Pixel>> {
+        self.frames.borrow().get(index).cloned()
+    }
+
+    pub fn last_frame(&self) -> Option<Vec<Pixel>> {
+        self.frames.borrow().last().cloned()
+    }
+}
+
+impl RenderBackend for HeadlessBackend {
+    fn present(
+        &mut self,
+        screen_buffer: &[Pixel],
+        previous: Option<&[Pixel]>,
+        _width: usize,
+    ) -> Result<FrameStats, Box<dyn Error>> {
+        self.frames.borrow_mut().push(screen_buffer.to_vec());
+
+        // Nothing is written anywhere, so only count the cells that changed
+        let cells_written = match previous {
+            Some(previous) => previous
+                .iter()
+                .zip(screen_buffer)
+                .filter(|(old, new)| old != new)
+                .count(),
+            None => screen_buffer.len(),
+        };
+
+        Ok(FrameStats {
+            cells_written,
+            bytes_written: 0,
+        })
+    }
+
+    fn get_colour_depth(&self) -> ColourDepth {
+        self.colour_depth
+    }
+}
+
+impl Default for HeadlessBackend {
+    fn default() -> HeadlessBackend {
+        HeadlessBackend::new()
+    }
+}
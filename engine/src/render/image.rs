@@ -0,0 +1,408 @@
+// Images loaded from PPM (or, with the png feature, PNG) files and shaded into
+// Sprites, so artwork can be brought into the console.
+use super::palette::Quantiser;
+use super::{Colour, Pixel, Sprite, Style};
+use std::error::Error;
+use std::fs;
+use std::io::{self, ErrorKind, Read};
+use std::path::Path;
+
+/// A width by height grid of RGB colours, where None is a transparent pixel.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Option<(u8, u8, u8)>>,
+}
+
+impl Image {
+    /// Creates a fully transparent image.
+    pub fn new(width: usize, height: usize) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![None; width * height],
+        }
+    }
+
+    /// Loads a .ppm file, or a .png file when the engine is built with the
+    /// png feature.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Image, Box<dyn Error>> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+        let bytes = fs::read(path)?;
+
+        match extension.as_deref() {
+            Some("ppm") => Ok(Image::read_ppm(&mut bytes.as_slice())?),
+            #[cfg(feature = "png")]
+            Some("png") => Image::read_png(&mut bytes.as_slice()),
+            #[cfg(not(feature = "png"))]
+            Some("png") => Err(invalid("PNG images need the engine's png feature").into()),
+            _ => Err(invalid(&format!("unsupported image type '{}'", path.display())).into()),
+        }
+    }
+
+    /// Reads a binary (P6) or plain text (P3) PPM image, scaling each channel
+    /// from the image's maximum value to 0-255.
+    pub fn read_ppm<R: Read>(reader: &mut R) -> io::Result<Image> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let mut header = PpmReader { bytes, position: 0 };
+        let binary = match header.read_token()?.as_str() {
+            "P6" => true,
+            "P3" => false,
+            magic => return Err(invalid(&format!("unsupported PPM type '{}'", magic))),
+        };
+        let width = header.read_number()? as usize;
+        let height = header.read_number()? as usize;
+        let max_value = header.read_number()?;
+
+        if max_value == 0 || max_value > 65535 {
+            return Err(invalid(&format!("invalid maximum value {}", max_value)));
+        }
+
+        // A single whitespace byte separates the header from binary samples
+        header.position += 1;
+
+        // Check the header against the data before allocating anything, so a
+        // corrupt size can't ask for more memory than the file could fill.
+        // Plain text samples take at least a byte each.
+        let sample_size = if binary && max_value > 255 { 2 } else { 1 };
+        let data_size = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3 * sample_size))
+            .ok_or_else(|| invalid(&format!("image size {}x{} is too large", width, height)))?;
+
+        if data_size > header.bytes.len().saturating_sub(header.position) {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+
+        let mut image = Image::new(width, height);
+        let mut read_sample = || -> io::Result<u8> {
+            let sample = match (binary, max_value > 255) {
+                (true, false) => header.read_bytes(1)?[0] as u32,
+                (true, true) => {
+                    let bytes = header.read_bytes(2)?;
+
+                    (bytes[0] as u32) << 8 | bytes[1] as u32
+                }
+                (false, _) => header.read_number()?,
+            };
+
+            if sample > max_value {
+                return Err(invalid(&format!("sample {} is over {}", sample, max_value)));
+            }
+
+            Ok((sample * 255 / max_value) as u8)
+        };
+
+        for pixel in image.pixels.iter_mut() {
+            *pixel = Some((read_sample()?, read_sample()?, read_sample()?));
+        }
+
+        Ok(image)
+    }
+
+    /// Reads a PNG image; pixels that are less than half opaque are
+    /// transparent.
+    #[cfg(feature = "png")]
+    pub fn read_png<R: Read>(reader: &mut R) -> Result<Image, Box<dyn Error>> {
+        use png::{ColorType, Decoder, Transformations};
+
+        let mut decoder = Decoder::new(reader);
+        decoder.set_transformations(Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let samples = info.color_type.samples();
+
+        let mut image = Image::new(info.width as usize, info.height as usize);
+
+        for (pixel, samples) in image.pixels.iter_mut().zip(buffer.chunks(samples)) {
+            *pixel = match (info.color_type, samples) {
+                (ColorType::Grayscale, &[grey]) => Some((grey, grey, grey)),
+                (ColorType::GrayscaleAlpha, &[grey, alpha]) => {
+                    Some((grey, grey, grey)).filter(|_| alpha >= 128)
+                }
+                (ColorType::Rgb, &[r, g, b]) => Some((r, g, b)),
+                (ColorType::Rgba, &[r, g, b, alpha]) => Some((r, g, b)).filter(|_| alpha >= 128),
+                (color_type, _) => {
+                    return Err(
+                        invalid(&format!("unsupported PNG colour type {:?}", color_type)).into(),
+                    )
+                }
+            };
+        }
+
+        Ok(image)
+    }
+
+    /// Resizes the image to width by height, averaging the pixels that land
+    /// in each new pixel. A new pixel is transparent if most of them were.
+    pub fn scale(&self, width: usize, height: usize) -> Image {
+        let mut scaled = Image::new(width, height);
+
+        if self.width == 0 || self.height == 0 {
+            return scaled;
+        }
+
+        for y in 0..height {
+            // Every new pixel covers at least one old one, even when enlarging
+            let top = y * self.height / height;
+            let bottom = ((y + 1) * self.height / height).max(top + 1);
+
+            for x in 0..width {
+                let left = x * self.width / width;
+                let right = ((x + 1) * self.width / width).max(left + 1);
+
+                let mut total = (0, 0, 0);
+                let mut opaque = 0;
+
+                for (r, g, b) in (top..bottom)
+                    .flat_map(|y| (left..right).map(move |x| (x, y)))
+                    .filter_map(|(x, y)| self.get_pixel(x, y))
+                {
+                    total = (
+                        total.0 + r as usize,
+                        total.1 + g as usize,
+                        total.2 + b as usize,
+                    );
+                    opaque += 1;
+                }
+
+                if opaque * 2 >= (right - left) * (bottom - top) {
+                    scaled.pixels[y * width + x] = Some((
+                        (total.0 / opaque) as u8,
+                        (total.1 / opaque) as u8,
+                        (total.2 / opaque) as u8,
+                    ));
+                }
+            }
+        }
+
+        scaled
+    }
+
+    /// Converts the image to a Sprite of the same size, drawing each pixel as
+    /// the shade glyph and pair of the 16 colours that mix to look closest to
+    /// it, ordered dithered by position. Transparent pixels stay transparent,
+    /// so solid cells are always drawn with a glyph rather than as a blank.
+    pub fn to_sprite(&self) -> Sprite {
+        let mut quantiser = Quantiser::new();
+        let mut sprite = Sprite::new(self.width, self.height);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Some(rgb) = self.get_pixel(x, y) {
+                    let (char_value, fg, bg) = quantiser.mix(rgb, (x, y));
+                    let style = Style::new(Colour::Indexed(fg), Colour::Indexed(bg));
+                    let pixel = Pixel::new(char_value, style);
+
+                    sprite.set_pixel(x as i32, y as i32, &pixel);
+                }
+            }
+        }
+
+        sprite
+    }
+
+    /// Sets a pixel; anything outside the image is skipped.
+    pub fn set_pixel(&mut self, x: usize, y: usize, rgb: Option<(u8, u8, u8)>) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = rgb;
+        }
+    }
+
+    /// Returns the colour at (x, y), or None if it's transparent or outside
+    /// the image.
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<(u8, u8, u8)> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+}
+
+// Reads the whitespace separated numbers of a PPM header, which may have
+// comments from '#' to the end of the line
+struct PpmReader {
+    bytes: Vec<u8>,
+    position: usize,
+}
+
+impl PpmReader {
+    fn read_token(&mut self) -> io::Result<String> {
+        loop {
+            match self.bytes.get(self.position) {
+                Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+                Some(b'#') => {
+                    while !matches!(self.bytes.get(self.position), Some(b'\n') | None) {
+                        self.position += 1;
+                    }
+                }
+                Some(_) => break,
+                None => return Err(ErrorKind::UnexpectedEof.into()),
+            }
+        }
+
+        let start = self.position;
+
+        while matches!(self.bytes.get(self.position), Some(byte) if !byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.position]).into_owned())
+    }
+
+    fn read_number(&mut self) -> io::Result<u32> {
+        let token = self.read_token()?;
+
+        token
+            .parse()
+            .map_err(|_| invalid(&format!("expected a number, found '{}'", token)))
+    }
+
+    fn read_bytes(&mut self, count: usize) -> io::Result<&[u8]> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + count)
+            .ok_or_else(|| io::Error::from(ErrorKind::UnexpectedEof))?;
+
+        self.position += count;
+
+        Ok(bytes)
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{colour, PIXEL, PIXEL_HALF};
+
+    #[test]
+    fn test_read_ppm() {
+        let text = b"P3\n# a comment\n2 1 15\n15 0 0  0 15 15\n";
+        let image = Image::read_ppm(&mut &text[..]).unwrap();
+
+        assert_eq!((image.get_width(), image.get_height()), (2, 1));
+        assert_eq!(image.get_pixel(0, 0), Some((255, 0, 0)));
+        assert_eq!(image.get_pixel(1, 0), Some((0, 255, 255)));
+
+        let mut binary = b"P6 1 2 255\n".to_vec();
+        binary.extend([1, 2, 3, 4, 5, 6]);
+        let image = Image::read_ppm(&mut binary.as_slice()).unwrap();
+
+        assert_eq!(image.get_pixel(0, 1), Some((4, 5, 6)));
+
+        // Samples over 255 are two bytes each
+        let mut wide = b"P6 1 1 65535\n".to_vec();
+        wide.extend([0xFF, 0xFF, 0x80, 0x00, 0x00, 0x00]);
+        let image = Image::read_ppm(&mut wide.as_slice()).unwrap();
+
+        assert_eq!(image.get_pixel(0, 0), Some((255, 127, 0)));
+
+        assert!(Image::read_ppm(&mut &b"P5 1 1 255\n\0"[..]).is_err());
+        assert!(Image::read_ppm(&mut &b"P6 2 2 255\n\0\0\0"[..]).is_err());
+        assert!(Image::read_ppm(&mut &b"P3 1 1 7\n1 2 8"[..]).is_err());
+    }
+
+    #[test]
+    fn test_read_ppm_size() {
+        let error = |bytes: &[u8]| Image::read_ppm(&mut &bytes[..]).unwrap_err().kind();
+
+        // Sizes that overflow are rejected, and sizes that don't are checked
+        // against the data rather than allocated up front
+        assert_eq!(
+            error(b"P6 4000000000 4000000000 255\n"),
+            ErrorKind::InvalidData
+        );
+        assert_eq!(
+            error(b"P6 100000 100000 255\n\0\0\0"),
+            ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            error(b"P3 100000 100000 255\n0 0 0"),
+            ErrorKind::UnexpectedEof
+        );
+        assert_eq!(error(b"P6 1 1 255"), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_scale() {
+        let mut image = Image::new(4, 2);
+
+        image.set_pixel(0, 0, Some((200, 0, 0)));
+        image.set_pixel(1, 0, Some((100, 0, 0)));
+        image.set_pixel(0, 1, Some((0, 0, 100)));
+        image.set_pixel(1, 1, Some((0, 0, 0)));
+        image.set_pixel(2, 0, Some((255, 255, 255)));
+
+        let scaled = image.scale(2, 1);
+
+        assert_eq!(scaled.get_pixel(0, 0), Some((75, 0, 25)));
+        // Only one of the four pixels on the right was opaque
+        assert_eq!(scaled.get_pixel(1, 0), None);
+
+        let enlarged = scaled.scale(4, 2);
+
+        assert_eq!(enlarged.get_pixel(1, 1), Some((75, 0, 25)));
+        assert_eq!(enlarged.get_pixel(2, 0), None);
+    }
+
+    #[test]
+    fn test_to_sprite() {
+        let mut image = Image::new(3, 1);
+
+        image.set_pixel(0, 0, Some((255, 0, 0)));
+        // Half way between dark red and black, which none of the 16 colours are
+        image.set_pixel(1, 0, Some((64, 0, 0)));
+
+        let sprite = image.to_sprite();
+
+        assert_eq!(sprite.get_pixel(0, 0).char_value, PIXEL);
+        assert_eq!(sprite.get_pixel(0, 0).attributes, colour::FG_RED);
+        assert_eq!(sprite.get_pixel(1, 0).char_value, PIXEL_HALF);
+        assert_eq!(
+            sprite.get_pixel(1, 0).attributes,
+            colour::FG_BLACK | colour::BG_DARK_RED
+        );
+        assert_eq!(sprite.get_pixel(2, 0).char_value, ' ');
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_read_png() {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&[10, 20, 30, 255, 40, 50, 60, 0])
+            .unwrap();
+
+        let image = Image::read_png(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(image.get_pixel(0, 0), Some((10, 20, 30)));
+        assert_eq!(image.get_pixel(1, 0), None);
+    }
+}
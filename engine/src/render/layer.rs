@@ -0,0 +1,114 @@
+// Named layers, composited over the Console's screen buffer when it is presented.
+use super::{Pixel, Rect, PIXEL_EMPTY};
+
+/// A screen-sized buffer of Pixels drawn over the Console's screen buffer.
+///
+/// Draw into a layer by selecting it with `Console::select_layer`. Cells equal
+/// to the layer's transparent key, PIXEL_EMPTY unless it is changed, let
+/// whatever is beneath show through.
+pub struct Layer {
+    name: String,
+    pixels: Vec<Pixel>,
+    z_index: i32,
+    visible: bool,
+    offset_x: i32,
+    offset_y: i32,
+    transparent_key: Pixel,
+}
+
+impl Layer {
+    pub(super) fn new(name: &str, z_index: i32, size: usize) -> Layer {
+        Layer {
+            name: name.to_string(),
+            pixels: vec![PIXEL_EMPTY; size],
+            z_index,
+            visible: true,
+            offset_x: 0,
+            offset_y: 0,
+            transparent_key: PIXEL_EMPTY,
+        }
+    }
+
+    pub(super) fn pixels_mut(&mut self) -> &mut [Pixel] {
+        &mut self.pixels
+    }
+
+    pub(super) fn pixels(&self) -> &[Pixel] {
+        &self.pixels
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Makes the whole layer transparent.
+    pub fn clear(&mut self) {
+        let key = self.transparent_key;
+
+        self.pixels.fill(key);
+    }
+
+    /// Higher layers are drawn over lower ones; layers with the same z-index
+    /// are drawn in the order they were added.
+    pub fn set_z_index(&mut self, z_index: i32) {
+        self.z_index = z_index;
+    }
+
+    pub fn get_z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Moves the whole layer; cell (x, y) of the layer is shown at
+    /// (x + offset_x, y + offset_y) on screen.
+    pub fn set_offset(&mut self, offset_x: i32, offset_y: i32) {
+        self.offset_x = offset_x;
+        self.offset_y = offset_y;
+    }
+
+    pub fn get_offset(&self) -> (i32, i32) {
+        (self.offset_x, self.offset_y)
+    }
+
+    /// Sets which Pixel counts as transparent; cells already drawn are left as
+    /// they are.
+    pub fn set_transparent_key(&mut self, key: Pixel) {
+        self.transparent_key = key;
+    }
+
+    pub fn get_transparent_key(&self) -> Pixel {
+        self.transparent_key
+    }
+}
+
+// Draws the visible layers over `frame`, from the lowest z-index up
+pub(super) fn composite(frame: &mut [Pixel], layers: &[Layer], width: usize) {
+    let bounds = Rect::from_size(width, frame.len() / width);
+
+    let mut visible: Vec<&Layer> = layers.iter().filter(|layer| layer.visible).collect();
+    visible.sort_by_key(|layer| layer.z_index);
+
+    for layer in visible {
+        for (index, pixel) in layer.pixels.iter().enumerate() {
+            if *pixel == layer.transparent_key {
+                continue;
+            }
+
+            let x = (index % width) as i32 + layer.offset_x;
+            let y = (index / width) as i32 + layer.offset_y;
+
+            if !bounds.contains(x, y) {
+                continue;
+            }
+
+            frame[y as usize * width + x as usize] = *pixel;
+        }
+    }
+}
@@ -0,0 +1,200 @@
+// Inline colour tags for text, e.g. "Score: [fg=yellow]1200[/]".
+use super::{Colour, Style};
+use std::error::Error;
+use std::fmt;
+
+/// A piece of text drawn in a single style.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TextRun {
+    pub text: String,
+    pub style: Style,
+}
+
+/// Why a piece of markup couldn't be parsed; `position` is the byte offset of
+/// the tag at fault.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MarkupError {
+    /// A tag other than `[fg=..]`, `[bg=..]` or `[/]`.
+    UnknownTag { tag: String, position: usize },
+    /// A colour that `Colour::from_str` can't read.
+    UnknownColour { name: String, position: usize },
+    /// A `[` without a `]` after it; write `[[` for a literal `[`.
+    UnclosedTag { position: usize },
+    /// A `[/]` with no tag left to close.
+    UnmatchedClose { position: usize },
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MarkupError::UnknownTag { tag, position } => {
+                write!(f, "unknown tag [{}] at byte {}", tag, position)
+            }
+            MarkupError::UnknownColour { name, position } => {
+                write!(f, "unknown colour '{}' at byte {}", name, position)
+            }
+            MarkupError::UnclosedTag { position } => {
+                write!(f, "tag at byte {} is missing its ']'", position)
+            }
+            MarkupError::UnmatchedClose { position } => {
+                write!(f, "[/] at byte {} has no tag to close", position)
+            }
+        }
+    }
+}
+
+impl Error for MarkupError {}
+
+/// Splits markup into runs of text, starting out in `style`.
+///
+/// `[fg=name]` and `[bg=name]` change the foreground or background colour
+/// until the matching `[/]`, and tags can be nested. Colours are written as
+/// `Colour::from_str` reads them, such as `yellow`, `dark_red` or `#ff8000`.
+pub fn parse_markup(markup: &str, style: Style) -> Result<Vec<TextRun>, MarkupError> {
+    let mut runs = Vec::new();
+    let mut text = String::new();
+    let mut stack = vec![style];
+    let mut rest = markup;
+
+    while let Some(start) = rest.find('[') {
+        text.push_str(&rest[..start]);
+
+        let position = markup.len() - rest.len() + start;
+        let after = &rest[start + 1..];
+
+        if let Some(after) = after.strip_prefix('[') {
+            text.push('[');
+            rest = after;
+            continue;
+        }
+
+        let end = after
+            .find(']')
+            .ok_or(MarkupError::UnclosedTag { position })?;
+        let tag = &after[..end];
+        let current = *stack.last().unwrap();
+
+        let next = match tag.split_once('=') {
+            None if tag == "/" => None,
+            Some(("fg", name)) => Some(Style {
+                fg: colour_by_name(name, position)?,
+                ..current
+            }),
+            Some(("bg", name)) => Some(Style {
+                bg: colour_by_name(name, position)?,
+                ..current
+            }),
+            _ => {
+                return Err(MarkupError::UnknownTag {
+                    tag: tag.to_string(),
+                    position,
+                })
+            }
+        };
+
+        // Finish the run so far in the colour it was written in
+        if !text.is_empty() {
+            runs.push(TextRun {
+                text: std::mem::take(&mut text),
+                style: current,
+            });
+        }
+
+        match next {
+            Some(style) => stack.push(style),
+            None if stack.len() > 1 => {
+                stack.pop();
+            }
+            None => return Err(MarkupError::UnmatchedClose { position }),
+        }
+
+        rest = &after[end + 1..];
+    }
+
+    text.push_str(rest);
+
+    if !text.is_empty() {
+        runs.push(TextRun {
+            text,
+            style: *stack.last().unwrap(),
+        });
+    }
+
+    Ok(runs)
+}
+
+fn colour_by_name(name: &str, position: usize) -> Result<Colour, MarkupError> {
+    name.parse().map_err(|_| MarkupError::UnknownColour {
+        name: name.to_string(),
+        position,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(text: &str, fg: Colour, bg: Colour) -> TextRun {
+        TextRun {
+            text: text.to_string(),
+            style: Style::new(fg, bg),
+        }
+    }
+
+    #[test]
+    fn test_parse_markup() {
+        let runs = parse_markup(
+            "Score: [fg=yellow]1200[/] [bg=dark_red]DANGER [fg=#ff8000]!![/][/] [[ok]",
+            Style::fg(Colour::GREY),
+        )
+        .unwrap();
+
+        let black = Colour::BLACK;
+        let dark_red = Colour::DARK_RED;
+
+        assert_eq!(
+            runs,
+            [
+                run("Score: ", Colour::GREY, black),
+                run("1200", Colour::YELLOW, black),
+                run(" ", Colour::GREY, black),
+                run("DANGER ", Colour::GREY, dark_red),
+                run("!!", Colour::Rgb(255, 128, 0), dark_red),
+                run(" [ok]", Colour::GREY, black),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markup_errors() {
+        assert_eq!(
+            parse_markup("ab[b]", Style::default()),
+            Err(MarkupError::UnknownTag {
+                tag: "b".to_string(),
+                position: 2
+            })
+        );
+        assert_eq!(
+            parse_markup("[fg=purple]", Style::default()),
+            Err(MarkupError::UnknownColour {
+                name: "purple".to_string(),
+                position: 0
+            })
+        );
+        assert_eq!(
+            parse_markup("x [fg=red", Style::default()),
+            Err(MarkupError::UnclosedTag { position: 2 })
+        );
+        assert_eq!(
+            parse_markup("[fg=red]x[/][/]", Style::default()),
+            Err(MarkupError::UnmatchedClose { position: 12 })
+        );
+
+        assert_eq!(
+            parse_markup("[fg=purple]", Style::default())
+                .unwrap_err()
+                .to_string(),
+            "unknown colour 'purple' at byte 0"
+        );
+    }
+}
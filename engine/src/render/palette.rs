@@ -0,0 +1,521 @@
+// Colours beyond the 16 of the attribute bits, and fitting them to what the
+// terminal can show.
+use super::{Pixel, PIXEL, PIXEL_HALF, PIXEL_QUARTER, PIXEL_THREE_QUARTERS};
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+// The 16 colours in `colour` FG_ order, as the Windows console shows them
+const PALETTE_16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (0, 0, 128),
+    (0, 128, 0),
+    (0, 128, 128),
+    (128, 0, 0),
+    (128, 0, 128),
+    (128, 128, 0),
+    (192, 192, 192),
+    (128, 128, 128),
+    (0, 0, 255),
+    (0, 255, 0),
+    (0, 255, 255),
+    (255, 0, 0),
+    (255, 0, 255),
+    (255, 255, 0),
+    (255, 255, 255),
+];
+
+// The names of the 16 colours in FG_ order, as in the `colour` module
+const NAMES: [&str; 16] = [
+    "black",
+    "dark_blue",
+    "dark_green",
+    "dark_cyan",
+    "dark_red",
+    "dark_magenta",
+    "dark_yellow",
+    "grey",
+    "dark_grey",
+    "blue",
+    "green",
+    "cyan",
+    "red",
+    "magenta",
+    "yellow",
+    "white",
+];
+
+// The levels of each channel in xterm's 6x6x6 colour cube, colours 16 to 231
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// The shade glyphs indexed by how many quarters of the cell their foreground
+// covers; a cell with none of it is drawn as a block in the background colour
+const SHADES: [char; 5] = [
+    PIXEL,
+    PIXEL_QUARTER,
+    PIXEL_HALF,
+    PIXEL_THREE_QUARTERS,
+    PIXEL,
+];
+
+// The 4x4 Bayer matrix: thresholds from 0 to 15 spread as evenly as possible,
+// so neighbouring cells round a colour between two shades different ways
+const BAYER: [[u32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// How many mixes the Quantiser remembers before starting again, since images
+// and gradients can go through far more colours than are worth keeping
+const MAX_MIXES: usize = 4096;
+
+/// A foreground or background colour.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Colour {
+    /// One of the 16 colours of the `colour` module, from 0 to 15 in FG_ order.
+    Indexed(u8),
+    /// One of the xterm 256-colour palette's colours.
+    Xterm(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Colour {
+    pub const BLACK: Colour = Colour::Indexed(0);
+    pub const DARK_BLUE: Colour = Colour::Indexed(1);
+    pub const DARK_GREEN: Colour = Colour::Indexed(2);
+    pub const DARK_CYAN: Colour = Colour::Indexed(3);
+    pub const DARK_RED: Colour = Colour::Indexed(4);
+    pub const DARK_MAGENTA: Colour = Colour::Indexed(5);
+    pub const DARK_YELLOW: Colour = Colour::Indexed(6);
+    pub const GREY: Colour = Colour::Indexed(7);
+    pub const DARK_GREY: Colour = Colour::Indexed(8);
+    pub const BLUE: Colour = Colour::Indexed(9);
+    pub const GREEN: Colour = Colour::Indexed(10);
+    pub const CYAN: Colour = Colour::Indexed(11);
+    pub const RED: Colour = Colour::Indexed(12);
+    pub const MAGENTA: Colour = Colour::Indexed(13);
+    pub const YELLOW: Colour = Colour::Indexed(14);
+    pub const WHITE: Colour = Colour::Indexed(15);
+
+    /// The colour's red, green and blue; the 16 colours are given as the
+    /// Windows console shows them, though terminals often differ.
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Colour::Indexed(index) => PALETTE_16[index as usize & 0x0F],
+            Colour::Xterm(index @ 0..=15) => PALETTE_16[indexed_to_ansi(index) as usize],
+            Colour::Xterm(index @ 16..=231) => {
+                let index = index as usize - 16;
+
+                (
+                    CUBE_LEVELS[index / 36],
+                    CUBE_LEVELS[index / 6 % 6],
+                    CUBE_LEVELS[index % 6],
+                )
+            }
+            Colour::Xterm(index) => {
+                let level = 8 + (index - 232) * 10;
+
+                (level, level, level)
+            }
+            Colour::Rgb(red, green, blue) => (red, green, blue),
+        }
+    }
+
+    /// The nearest colour in the xterm 256-colour palette. The 16 colours keep
+    /// their own palette entries, so they look the same as they do in 16 colours.
+    pub fn to_xterm(self) -> u8 {
+        let (red, green, blue) = match self {
+            Colour::Indexed(index) => return indexed_to_ansi(index & 0x0F),
+            Colour::Xterm(index) => return index,
+            Colour::Rgb(red, green, blue) => (red, green, blue),
+        };
+
+        // RGB colours only go to the cube and the grey ramp; terminals are
+        // free to change the first 16
+        let level = |channel: u8| {
+            (0..CUBE_LEVELS.len())
+                .min_by_key(|&level| (CUBE_LEVELS[level] as i32 - channel as i32).abs())
+                .unwrap()
+        };
+        let cube = 16 + 36 * level(red) + 6 * level(green) + level(blue);
+
+        let average = (red as u32 + green as u32 + blue as u32) / 3;
+        let grey = 232 + (average.saturating_sub(3) / 10).min(23) as usize;
+
+        let error = |index: u8| distance(Colour::Xterm(index).to_rgb(), (red, green, blue));
+
+        if error(cube as u8) <= error(grey as u8) {
+            cube as u8
+        } else {
+            grey as u8
+        }
+    }
+
+    /// The nearest of the 16 colours, from 0 to 15 in FG_ order.
+    pub fn to_indexed(self) -> u8 {
+        match self {
+            Colour::Indexed(index) => index & 0x0F,
+            Colour::Xterm(index @ 0..=15) => indexed_to_ansi(index),
+            _ => nearest_16(self.to_rgb()),
+        }
+    }
+}
+
+/// Reads the lower case name of one of the 16 colours, such as `dark_red`,
+/// where the bright ones can also be written like `bright_red`; or an RGB
+/// colour written like `#ff8000`.
+impl FromStr for Colour {
+    type Err = ParseColourError;
+
+    fn from_str(name: &str) -> Result<Colour, ParseColourError> {
+        let unknown = || ParseColourError {
+            name: name.to_string(),
+        };
+
+        if let Some(hex) = name.strip_prefix('#') {
+            let channel = |start: usize| {
+                hex.get(start..start + 2)
+                    .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+                    .ok_or_else(unknown)
+            };
+
+            if hex.len() != 6 {
+                return Err(unknown());
+            }
+
+            return Ok(Colour::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+
+        let index = match name.strip_prefix("bright_") {
+            // Only the bright half of the colours, which have dark_ versions
+            Some(bright) => NAMES[9..]
+                .iter()
+                .position(|&colour| colour == bright)
+                .map(|index| index + 9),
+            None => NAMES.iter().position(|&colour| colour == name),
+        };
+
+        index
+            .map(|index| Colour::Indexed(index as u8))
+            .ok_or_else(unknown)
+    }
+}
+
+/// A colour name that `Colour::from_str` didn't recognise.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseColourError {
+    pub name: String,
+}
+
+impl fmt::Display for ParseColourError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown colour '{}'", self.name)
+    }
+}
+
+impl Error for ParseColourError {}
+
+/// How many colours a backend can show.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum ColourDepth {
+    /// The 16 colours of the `colour` module.
+    Indexed16,
+    Xterm256,
+    TrueColour,
+}
+
+impl ColourDepth {
+    /// Guesses what the terminal supports from the COLORTERM and TERM
+    /// environment variables.
+    pub fn detect() -> ColourDepth {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColourDepth::TrueColour
+        } else if term.contains("256color") {
+            ColourDepth::Xterm256
+        } else {
+            ColourDepth::Indexed16
+        }
+    }
+}
+
+// Fits Pixels' colours to a backend's colour depth, remembering which two of
+// the 16 colours each RGB colour is mixed from since the search isn't cheap
+#[derive(Default)]
+pub(crate) struct Quantiser {
+    mixes: HashMap<(u8, u8, u8), (u8, u8, u32)>,
+}
+
+impl Quantiser {
+    pub fn new() -> Quantiser {
+        Quantiser::default()
+    }
+
+    // With `dither`, cells that show one flat colour (a PIXEL's foreground or a
+    // blank's background) are drawn in 16 colours as the shade glyph and pair
+    // of colours that mix to look closest to it; (x, y) is the cell's position
+    // for `mix`
+    pub fn fit(
+        &mut self,
+        pixel: &Pixel,
+        (x, y): (usize, usize),
+        depth: ColourDepth,
+        dither: bool,
+    ) -> Pixel {
+        let (fg, bg) = match pixel.colours {
+            Some(colours) => colours,
+            None => return *pixel,
+        };
+
+        match depth {
+            ColourDepth::TrueColour => *pixel,
+            ColourDepth::Xterm256 => {
+                let fit = |colour| match colour {
+                    Colour::Rgb(..) => Colour::Xterm(colour.to_xterm()),
+                    colour => colour,
+                };
+
+                Pixel {
+                    colours: Some((fit(fg), fit(bg))),
+                    ..*pixel
+                }
+            }
+            ColourDepth::Indexed16 => {
+                let flat = match pixel.char_value {
+                    PIXEL => Some(fg),
+                    ' ' => Some(bg),
+                    _ => None,
+                };
+
+                let (char_value, fg, bg) = match flat {
+                    Some(colour @ (Colour::Rgb(..) | Colour::Xterm(16..))) if dither => {
+                        self.mix(colour.to_rgb(), (x, y))
+                    }
+                    _ => (pixel.char_value, fg.to_indexed(), bg.to_indexed()),
+                };
+
+                Pixel {
+                    char_value,
+                    attributes: pixel.attributes & 0xFF00 | (bg as u16) << 4 | fg as u16,
+                    colours: None,
+                }
+            }
+        }
+    }
+
+    // The shade glyph and pair of the 16 colours, foreground first, that draw
+    // `target` in the cell at (x, y). Colours between two shades are ordered
+    // dithered, so a patch of them mixes the shades on either side.
+    pub fn mix(&mut self, target: (u8, u8, u8), (x, y): (usize, usize)) -> (char, u8, u8) {
+        let (fg, bg, coverage) = match self.mixes.get(&target) {
+            Some(mix) => *mix,
+            None => {
+                if self.mixes.len() >= MAX_MIXES {
+                    self.mixes.clear();
+                }
+
+                let mix = nearest_mix(target);
+                self.mixes.insert(target, mix);
+
+                mix
+            }
+        };
+
+        // Coverage in 64ths of the cell is 16ths of a quarter, and adding the
+        // threshold (half way into one of 16 steps) rounds it to a quarter;
+        // both are counted in 32nds of a quarter to keep the half step whole
+        let threshold = BAYER[y % 4][x % 4] * 2 + 1;
+        let quarters = ((coverage * 2 + threshold) / 32).min(4) as usize;
+
+        match quarters {
+            0 => (SHADES[0], bg, bg),
+            quarters => (SHADES[quarters], fg, bg),
+        }
+    }
+}
+
+// The pair of the 16 colours whose blend comes closest to `target`, and how
+// many 64ths of the cell the first should cover
+fn nearest_mix(target: (u8, u8, u8)) -> (u8, u8, u32) {
+    let mut best = (nearest_16(target), 0, 64);
+    let mut best_distance = distance(PALETTE_16[best.0 as usize], target);
+
+    for fg in 0..16u8 {
+        for bg in 0..16u8 {
+            let (a, b) = (PALETTE_16[fg as usize], PALETTE_16[bg as usize]);
+            let channels = [
+                (a.0, b.0, target.0),
+                (a.1, b.1, target.1),
+                (a.2, b.2, target.2),
+            ];
+
+            // Project the target onto the line from bg to fg
+            let along: i32 = (channels.iter())
+                .map(|&(a, b, t)| (a as i32 - b as i32) * (t as i32 - b as i32))
+                .sum();
+            let length: i32 = (channels.iter())
+                .map(|&(a, b, _)| (a as i32 - b as i32).pow(2))
+                .sum();
+
+            if length == 0 {
+                continue;
+            }
+
+            let coverage = (along * 64 + length / 2).div_euclid(length).clamp(0, 64) as u32;
+            let blend =
+                |a: u8, b: u8| ((a as u32 * coverage + b as u32 * (64 - coverage)) / 64) as u8;
+            let mixed = (blend(a.0, b.0), blend(a.1, b.1), blend(a.2, b.2));
+            let mixed_distance = distance(mixed, target);
+
+            if mixed_distance < best_distance {
+                best = (fg, bg, coverage);
+                best_distance = mixed_distance;
+            }
+        }
+    }
+
+    best
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let square = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+
+    square(a.0, b.0) + square(a.1, b.1) + square(a.2, b.2)
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> u8 {
+    (0..16u8)
+        .min_by_key(|&index| distance(PALETTE_16[index as usize], rgb))
+        .unwrap()
+}
+
+// Windows colour bits are ordered blue, green, red; ANSI colours are ordered
+// red, green, blue, and both keep brightness in the top bit. Swapping red and
+// blue works both ways, so this also turns ANSI colours into Windows ones.
+pub(crate) fn indexed_to_ansi(index: u8) -> u8 {
+    (index & 0x08) | (index & 0x01) << 2 | (index & 0x02) | (index & 0x04) >> 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{colour, Style};
+
+    #[test]
+    fn test_colour_conversions() {
+        assert_eq!(Colour::Indexed(12).to_rgb(), (255, 0, 0));
+        // xterm's red is 1, not the Windows 4
+        assert_eq!(Colour::Xterm(1).to_rgb(), (128, 0, 0));
+        assert_eq!(Colour::Xterm(196).to_rgb(), (255, 0, 0));
+        assert_eq!(Colour::Xterm(244).to_rgb(), (128, 128, 128));
+
+        assert_eq!(Colour::Rgb(250, 10, 0).to_xterm(), 196);
+        assert_eq!(Colour::Rgb(120, 121, 119).to_xterm(), 243);
+        assert_eq!(Colour::Indexed(4).to_xterm(), 1);
+
+        assert_eq!(Colour::Rgb(200, 20, 30).to_indexed(), 12);
+        assert_eq!(Colour::Xterm(1).to_indexed(), 4);
+        assert_eq!(Colour::Rgb(90, 90, 100).to_indexed(), 8);
+    }
+
+    #[test]
+    fn test_parse_colour() {
+        assert_eq!("dark_red".parse(), Ok(Colour::DARK_RED));
+        assert_eq!("red".parse(), Ok(Colour::RED));
+        assert_eq!("bright_red".parse(), Ok(Colour::RED));
+        assert_eq!("#FF8000".parse(), Ok(Colour::Rgb(255, 128, 0)));
+
+        for name in [
+            "bright_dark_red",
+            "bright_black",
+            "purple",
+            "#ff80",
+            "#ff80zz",
+        ] {
+            assert_eq!(
+                name.parse::<Colour>(),
+                Err(ParseColourError {
+                    name: name.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_fit() {
+        let mut quantiser = Quantiser::new();
+        let orange = Pixel::new('#', Style::new(Colour::Rgb(255, 128, 0), Colour::Xterm(17)));
+
+        assert_eq!(
+            quantiser.fit(&orange, (0, 0), ColourDepth::TrueColour, false),
+            orange
+        );
+
+        let fitted = quantiser.fit(&orange, (0, 0), ColourDepth::Xterm256, false);
+        assert_eq!(
+            fitted.colours,
+            Some((Colour::Xterm(208), Colour::Xterm(17)))
+        );
+
+        let fitted = quantiser.fit(&orange, (0, 0), ColourDepth::Indexed16, true);
+        assert_eq!(fitted.char_value, '#');
+        assert_eq!(fitted.colours, None);
+        assert_eq!(fitted.attributes, orange.attributes);
+
+        // A flat colour is dithered into a shade of two of the 16
+        let block = Pixel::new(PIXEL, Style::fg(Colour::Rgb(64, 0, 0)));
+        let fitted = quantiser.fit(&block, (0, 0), ColourDepth::Indexed16, true);
+
+        assert_eq!(fitted.char_value, PIXEL_HALF);
+        assert_eq!(fitted.attributes, colour::FG_BLACK | colour::BG_DARK_RED);
+
+        let fitted = quantiser.fit(&block, (0, 0), ColourDepth::Indexed16, false);
+        assert_eq!(fitted.char_value, PIXEL);
+        assert_eq!(fitted.attributes, colour::FG_BLACK | colour::BG_BLACK);
+
+        // A colour between two shades is ordered dithered, half of a 4x4
+        // patch taking the shade on either side
+        let glyphs: Vec<char> = (0..16)
+            .map(|index| quantiser.mix((48, 0, 0), (index % 4, index / 4)))
+            .map(|(glyph, fg, bg)| {
+                assert_eq!((fg, bg), (0, 4));
+                glyph
+            })
+            .collect();
+
+        assert_eq!(
+            &glyphs[..4],
+            [
+                PIXEL_HALF,
+                PIXEL_THREE_QUARTERS,
+                PIXEL_HALF,
+                PIXEL_THREE_QUARTERS
+            ]
+        );
+        assert_eq!(
+            glyphs.iter().filter(|&&glyph| glyph == PIXEL_HALF).count(),
+            8
+        );
+
+        // Remembered mixes are capped
+        for red in 0..=255 {
+            for green in 0..17 {
+                quantiser.mix((red, green, 0), (0, 0));
+            }
+        }
+        assert!(quantiser.mixes.len() <= MAX_MIXES);
+        assert_eq!(quantiser.mix((48, 0, 0), (1, 0)).0, PIXEL_THREE_QUARTERS);
+
+        // Pixels in the 16 colours are left alone
+        let plain = Pixel {
+            char_value: PIXEL,
+            attributes: colour::FG_RED,
+            colours: None,
+        };
+        assert_eq!(
+            quantiser.fit(&plain, (0, 0), ColourDepth::Indexed16, true),
+            plain
+        );
+    }
+}
@@ -0,0 +1,301 @@
+// Screenshots of a frame as ANSI text, an HTML page or a PPM image, for
+// attaching what the player saw to bug reports.
+use super::ansi::write_sgr;
+use super::font::{self, CELL_HEIGHT, CELL_WIDTH};
+use super::text::{cell_glyph, char_width};
+use super::{Pixel, Style};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The file formats `Console::write_screenshot` can produce.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ScreenshotFormat {
+    /// Text with ANSI escape sequences for the colours, which `cat` shows in
+    /// a terminal.
+    Ansi,
+    /// A standalone page with a coloured `<span>` for each run of cells.
+    Html,
+    /// A binary PPM image, drawn with a built-in 6x8 pixel font.
+    Ppm,
+}
+
+impl ScreenshotFormat {
+    pub const ALL: [ScreenshotFormat; 3] = [
+        ScreenshotFormat::Ansi,
+        ScreenshotFormat::Html,
+        ScreenshotFormat::Ppm,
+    ];
+
+    /// Picks the format from a path's extension: .ans, .html (or .htm) or .ppm.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<ScreenshotFormat> {
+        let extension = path.as_ref().extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "ans" => Some(ScreenshotFormat::Ansi),
+            "html" | "htm" => Some(ScreenshotFormat::Html),
+            "ppm" => Some(ScreenshotFormat::Ppm),
+            _ => None,
+        }
+    }
+
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            ScreenshotFormat::Ansi => "ans",
+            ScreenshotFormat::Html => "html",
+            ScreenshotFormat::Ppm => "ppm",
+        }
+    }
+}
+
+pub(crate) fn write_screenshot<W: Write>(
+    frame: &[Pixel],
+    width: usize,
+    format: ScreenshotFormat,
+    out: &mut W,
+) -> io::Result<()> {
+    match format {
+        ScreenshotFormat::Ansi => write_ansi(frame, width, out),
+        ScreenshotFormat::Html => write_html(frame, width, out),
+        ScreenshotFormat::Ppm => write_ppm(frame, width, out),
+    }
+}
+
+// Each row starts from the terminal's own colours and resets them at the end,
+// so the file can be shown anywhere on the screen
+fn write_ansi<W: Write>(frame: &[Pixel], width: usize, out: &mut W) -> io::Result<()> {
+    for (y, row) in frame.chunks(width).enumerate() {
+        let mut current = None;
+
+        for (x, pixel) in row.iter().enumerate() {
+            let glyph = match cell_glyph(frame, y * width + x, width) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            let style = pixel.get_style();
+
+            if current != Some(style) {
+                write_sgr(out, &style, true)?;
+                current = Some(style);
+            }
+
+            write!(out, "{}", glyph)?;
+        }
+
+        write!(out, "\x1b[0m\r\n")?;
+    }
+
+    Ok(())
+}
+
+fn write_html<W: Write>(frame: &[Pixel], width: usize, out: &mut W) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Screenshot</title>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body style=\"background: #000000\">")?;
+    write!(
+        out,
+        "<pre style=\"font-family: monospace; line-height: 1\">"
+    )?;
+
+    for (y, row) in frame.chunks(width).enumerate() {
+        let mut current = None;
+
+        for (x, pixel) in row.iter().enumerate() {
+            let glyph = match cell_glyph(frame, y * width + x, width) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            let style = pixel.get_style();
+
+            if current != Some(style) {
+                if current.is_some() {
+                    write!(out, "</span>")?;
+                }
+
+                write!(out, "<span style=\"{}\">", css(&style))?;
+                current = Some(style);
+            }
+
+            match glyph {
+                '&' => write!(out, "&amp;")?,
+                '<' => write!(out, "&lt;")?,
+                '>' => write!(out, "&gt;")?,
+                glyph => write!(out, "{}", glyph)?,
+            }
+        }
+
+        if current.is_some() {
+            write!(out, "</span>")?;
+        }
+
+        writeln!(out)?;
+    }
+
+    writeln!(out, "</pre>")?;
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
+
+fn css(style: &Style) -> String {
+    let (fg, bg) = colours(style);
+    let mut css = format!("color: {}; background: {}", hex(fg), hex(bg));
+
+    if style.bold {
+        css.push_str("; font-weight: bold");
+    }
+    if style.underline {
+        css.push_str("; text-decoration: underline");
+    }
+
+    css
+}
+
+fn hex((red, green, blue): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", red, green, blue)
+}
+
+// The colours a style's text and background are shown in, once reverse has
+// swapped them
+fn colours(style: &Style) -> ((u8, u8, u8), (u8, u8, u8)) {
+    let (fg, bg) = (style.fg.to_rgb(), style.bg.to_rgb());
+
+    if style.reverse {
+        (bg, fg)
+    } else {
+        (fg, bg)
+    }
+}
+
+// Bold text is drawn twice, one pixel apart, and underlines take up the font's
+// bottom row
+fn write_ppm<W: Write>(frame: &[Pixel], width: usize, out: &mut W) -> io::Result<()> {
+    let height = frame.len() / width;
+    let (image_width, image_height) = (width * CELL_WIDTH, height * CELL_HEIGHT);
+    let mut image = vec![0; image_width * image_height * 3];
+
+    for (index, pixel) in frame.iter().enumerate() {
+        let glyph = match cell_glyph(frame, index, width) {
+            Some(glyph) => glyph,
+            None => continue,
+        };
+        let style = pixel.get_style();
+        let (fg, bg) = colours(&style);
+        let columns = char_width(glyph).clamp(1, width - index % width);
+
+        for y in 0..CELL_HEIGHT {
+            for x in 0..columns * CELL_WIDTH {
+                let is_lit = font::is_lit(glyph, columns, x, y)
+                    || (style.bold && x > 0 && font::is_lit(glyph, columns, x - 1, y))
+                    || (style.underline && y == CELL_HEIGHT - 1);
+                let (red, green, blue) = if is_lit { fg } else { bg };
+
+                let image_x = index % width * CELL_WIDTH + x;
+                let image_y = index / width * CELL_HEIGHT + y;
+                let offset = (image_y * image_width + image_x) * 3;
+
+                image[offset..offset + 3].copy_from_slice(&[red, green, blue]);
+            }
+        }
+    }
+
+    write!(out, "P6\n{} {}\n255\n", image_width, image_height)?;
+    out.write_all(&image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Colour, Console, HeadlessBackend};
+    use super::*;
+    use crate::render::Image;
+
+    fn console() -> Console {
+        let mut console = Console::with_backend(4, 2, Box::new(HeadlessBackend::new()));
+
+        console.draw_string(0, 0, "a<", Style::new(Colour::WHITE, Colour::DARK_BLUE));
+        console.draw_string(2, 0, "日", Style::fg(Colour::Rgb(255, 128, 0)));
+        console.draw_string(
+            0,
+            1,
+            "b",
+            Style {
+                underline: true,
+                ..Style::fg(Colour::WHITE)
+            },
+        );
+
+        console
+    }
+
+    fn screenshot(console: &Console, format: ScreenshotFormat) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        console.write_screenshot(format, &mut out).unwrap();
+
+        out
+    }
+
+    #[test]
+    fn test_ansi() {
+        let ansi = screenshot(&console(), ScreenshotFormat::Ansi);
+
+        assert_eq!(
+            String::from_utf8(ansi).unwrap(),
+            "\x1b[0;97;44ma<\x1b[0;38;2;255;128;0;40m日\x1b[0m\r\n\
+             \x1b[0;4;97;40mb\x1b[0;30;40m   \x1b[0m\r\n"
+        );
+    }
+
+    #[test]
+    fn test_html() {
+        let html = String::from_utf8(screenshot(&console(), ScreenshotFormat::Html)).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains(
+            "<span style=\"color: #ffffff; background: #000080\">a&lt;</span>\
+             <span style=\"color: #ff8000; background: #000000\">日</span>\n"
+        ));
+        assert!(html.contains(
+            "<span style=\"color: #ffffff; background: #000000; \
+             text-decoration: underline\">b</span>"
+        ));
+    }
+
+    #[test]
+    fn test_ppm() {
+        let ppm = screenshot(&console(), ScreenshotFormat::Ppm);
+        let image = Image::read_ppm(&mut ppm.as_slice()).unwrap();
+
+        assert_eq!(
+            (image.get_width(), image.get_height()),
+            (4 * CELL_WIDTH, 2 * CELL_HEIGHT)
+        );
+        // The corner of 'a' is background, and the top of its bowl foreground
+        assert_eq!(image.get_pixel(0, 0), Some((0, 0, 128)));
+        assert_eq!(image.get_pixel(1, 2), Some((255, 255, 255)));
+        // The wide character's box spans both of its cells
+        assert_eq!(image.get_pixel(2 * CELL_WIDTH, 0), Some((255, 128, 0)));
+        assert_eq!(image.get_pixel(4 * CELL_WIDTH - 2, 0), Some((255, 128, 0)));
+        // The underline runs under the whole cell
+        assert_eq!(
+            image.get_pixel(CELL_WIDTH - 1, 2 * CELL_HEIGHT - 1),
+            Some((255, 255, 255))
+        );
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ScreenshotFormat::from_path("shot.HTM"),
+            Some(ScreenshotFormat::Html)
+        );
+        assert_eq!(
+            ScreenshotFormat::from_path("a/b.ans"),
+            Some(ScreenshotFormat::Ansi)
+        );
+        assert_eq!(ScreenshotFormat::from_path("shot.png"), None);
+        assert_eq!(ScreenshotFormat::from_path("shot"), None);
+    }
+}
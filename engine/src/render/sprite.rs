@@ -0,0 +1,237 @@
+// Sprites, stored on disk in olcConsoleGameEngine's .spr format.
+use super::{Canvas, Pixel, Rect, PIXEL_EMPTY};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+// The most cells `Sprite::read_from` will allocate, so a corrupt header fails
+// to load rather than asking for gigabytes
+const MAX_FILE_CELLS: usize = 4096 * 4096;
+
+/// A width by height grid of Pixels.
+///
+/// Cells whose char_value is ' ' are transparent: `Console::draw_sprite` skips
+/// them, so whatever was underneath shows through.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Sprite {
+    width: usize,
+    height: usize,
+    pixels: Vec<Pixel>,
+}
+
+impl Sprite {
+    /// Creates a fully transparent sprite.
+    pub fn new(width: usize, height: usize) -> Sprite {
+        Sprite {
+            width,
+            height,
+            pixels: vec![PIXEL_EMPTY; width * height],
+        }
+    }
+
+    /// Loads a sprite saved by this engine or by olcConsoleGameEngine.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Sprite, Box<dyn Error>> {
+        let file = File::open(path)?;
+
+        Ok(Sprite::read_from(&mut BufReader::new(file))?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Reads the .spr format: the width and height as little-endian i32s, then
+    /// every cell's colour as an i16, then every cell's glyph as a UTF-16 i16.
+    /// Files claiming more than 4096x4096 cells are rejected as invalid.
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Sprite> {
+        let width = read_i32(reader)?;
+        let height = read_i32(reader)?;
+
+        let cells = (width as usize).checked_mul(height as usize);
+
+        if width < 0 || height < 0 || cells.is_none_or(|cells| cells > MAX_FILE_CELLS) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid sprite size {}x{}", width, height),
+            ));
+        }
+
+        let mut sprite = Sprite::new(width as usize, height as usize);
+
+        for pixel in sprite.pixels.iter_mut() {
+            pixel.attributes = read_i16(reader)? as u16;
+        }
+
+        for pixel in sprite.pixels.iter_mut() {
+            let glyph = read_i16(reader)? as u16;
+
+            pixel.char_value = char::from_u32(glyph as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+        }
+
+        Ok(sprite)
+    }
+
+    /// Writes the .spr format; glyphs outside the Basic Multilingual Plane don't
+    /// fit in one UTF-16 unit and are saved as U+FFFD, and only the 16 colours
+    /// of `attributes` are saved.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&(self.width as i32).to_le_bytes())?;
+        writer.write_all(&(self.height as i32).to_le_bytes())?;
+
+        for pixel in self.pixels.iter() {
+            writer.write_all(&pixel.attributes.to_le_bytes())?;
+        }
+
+        for pixel in self.pixels.iter() {
+            let mut glyph = [0; 2];
+            let glyph = match pixel.char_value.encode_utf16(&mut glyph) {
+                [unit] => *unit,
+                _ => char::REPLACEMENT_CHARACTER as u16,
+            };
+
+            writer.write_all(&glyph.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Changes the size of the sprite, keeping what overlaps the old size and
+    /// leaving any new cells transparent.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let mut resized = Sprite::new(width, height);
+
+        for y in 0..height.min(self.height) {
+            for x in 0..width.min(self.width) {
+                resized.pixels[y * width + x] = self.get_pixel(x, y);
+            }
+        }
+
+        *self = resized;
+    }
+
+    /// Sets a Pixel; anything outside the sprite is skipped.
+    pub fn set_pixel(&mut self, x: i32, y: i32, pixel: &Pixel) {
+        if !Rect::from_size(self.width, self.height).contains(x, y) {
+            return;
+        }
+
+        self.pixels[y as usize * self.width + x as usize] = *pixel;
+    }
+
+    /// Returns the Pixel at (x, y), or PIXEL_EMPTY outside the sprite.
+    pub fn get_pixel(&self, x: usize, y: usize) -> Pixel {
+        if x >= self.width || y >= self.height {
+            return PIXEL_EMPTY;
+        }
+
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+}
+
+impl Canvas for Sprite {
+    type Ink = Pixel;
+
+    fn plot(&mut self, x: i32, y: i32, pixel: &Pixel) {
+        self.set_pixel(x, y, pixel);
+    }
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> io::Result<i32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(i32::from_le_bytes(bytes))
+}
+
+fn read_i16<R: Read>(reader: &mut R) -> io::Result<i16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(i16::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{colour, PIXEL};
+
+    #[test]
+    fn test_sprite_file_format() {
+        let mut sprite = Sprite::new(2, 1);
+        sprite.set_pixel(
+            1,
+            0,
+            &Pixel {
+                char_value: PIXEL,
+                attributes: colour::FG_RED | colour::BG_DARK_BLUE,
+                colours: None,
+            },
+        );
+
+        let mut bytes = Vec::new();
+        sprite.write_to(&mut bytes).unwrap();
+
+        // Byte for byte what olcConsoleGameEngine writes for the same sprite
+        let expected = [
+            2, 0, 0, 0, 1, 0, 0, 0, // width and height
+            0, 0, 0x1C, 0, // colours
+            b' ', 0, 0x88, 0x25, // glyphs, ' ' and U+2588
+        ];
+        assert_eq!(bytes, expected);
+
+        assert_eq!(Sprite::read_from(&mut &bytes[..]).unwrap(), sprite);
+
+        // Truncated files and negative sizes are rejected
+        assert!(Sprite::read_from(&mut &bytes[..bytes.len() - 1]).is_err());
+        assert!(Sprite::read_from(&mut &[0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0][..]).is_err());
+
+        // Sizes too big to be a real sprite are rejected before allocating
+        let huge = [0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0xFF, 0xFF, 0x7F];
+        let error = Sprite::read_from(&mut &huge[..]).unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_sprite_pixels() {
+        let mut sprite = Sprite::new(3, 2);
+        let pixel = Pixel {
+            char_value: 'X',
+            attributes: colour::FG_WHITE,
+            colours: None,
+        };
+
+        sprite.set_pixel(2, 1, &pixel);
+        sprite.set_pixel(3, 0, &pixel);
+        sprite.set_pixel(-1, 0, &pixel);
+
+        assert_eq!(sprite.get_pixel(2, 1), pixel);
+        assert_eq!(sprite.pixels.iter().filter(|&&p| p == pixel).count(), 1);
+        assert_eq!(sprite.get_pixel(5, 5), PIXEL_EMPTY);
+
+        sprite.resize(4, 1);
+        sprite.set_pixel(3, 0, &pixel);
+
+        assert_eq!(sprite.get_pixel(2, 1), PIXEL_EMPTY);
+        assert_eq!(sprite.get_pixel(3, 0), pixel);
+
+        sprite.resize(4, 2);
+
+        assert_eq!(sprite.get_pixel(3, 0), pixel);
+        assert_eq!(sprite.get_pixel(3, 1), PIXEL_EMPTY);
+    }
+}
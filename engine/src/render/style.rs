@@ -0,0 +1,111 @@
+// Typed colours and text effects, in place of ORing `colour` constants together.
+use super::{colour, Colour};
+
+/// How text and Pixels are drawn.
+///
+/// Start from `Style::new` or `Style::fg` and override what you need:
+///
+/// ```
+/// # use engine::render::{Colour, Style};
+/// let warning = Style {
+///     bold: true,
+///     ..Style::new(Colour::YELLOW, Colour::DARK_RED)
+/// };
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Style {
+    pub fg: Colour,
+    pub bg: Colour,
+    /// Not shown by the Windows console.
+    pub bold: bool,
+    pub underline: bool,
+    /// Swaps the foreground and background.
+    pub reverse: bool,
+}
+
+impl Style {
+    pub fn new(fg: Colour, bg: Colour) -> Style {
+        Style {
+            fg,
+            bg,
+            bold: false,
+            underline: false,
+            reverse: false,
+        }
+    }
+
+    /// `fg` on black.
+    pub fn fg(fg: Colour) -> Style {
+        Style::new(fg, Colour::BLACK)
+    }
+
+    /// Reads the colours and effects out of `colour` attribute bits.
+    pub fn from_attributes(attributes: u16) -> Style {
+        Style {
+            fg: Colour::Indexed((attributes & 0x000F) as u8),
+            bg: Colour::Indexed(((attributes & 0x00F0) >> 4) as u8),
+            bold: attributes & colour::BOLD != 0,
+            underline: attributes & colour::UNDERLINE != 0,
+            reverse: attributes & colour::REVERSE != 0,
+        }
+    }
+
+    /// The attribute bits for the style, with the nearest of the 16 colours
+    /// to each of its colours.
+    pub fn to_attributes(&self) -> u16 {
+        let mut attributes = (self.bg.to_indexed() as u16) << 4 | self.fg.to_indexed() as u16;
+
+        if self.bold {
+            attributes |= colour::BOLD;
+        }
+        if self.underline {
+            attributes |= colour::UNDERLINE;
+        }
+        if self.reverse {
+            attributes |= colour::REVERSE;
+        }
+
+        attributes
+    }
+}
+
+/// Grey on black, like a terminal's default colours.
+impl Default for Style {
+    fn default() -> Style {
+        Style::fg(Colour::GREY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attributes() {
+        let style =
+            Style::from_attributes(colour::FG_WHITE | colour::BG_DARK_RED | colour::UNDERLINE);
+
+        assert_eq!(
+            style,
+            Style {
+                underline: true,
+                ..Style::new(Colour::WHITE, Colour::DARK_RED)
+            }
+        );
+        assert_eq!(
+            style.to_attributes(),
+            colour::FG_WHITE | colour::BG_DARK_RED | colour::UNDERLINE
+        );
+
+        // Colours outside the 16 come out as the nearest of them
+        let orange = Style {
+            bold: true,
+            ..Style::fg(Colour::Rgb(250, 10, 0))
+        };
+
+        assert_eq!(
+            orange.to_attributes(),
+            colour::FG_RED | colour::BG_BLACK | colour::BOLD
+        );
+    }
+}
@@ -0,0 +1,171 @@
+// Measuring, wrapping and truncating text by the columns it takes up on screen.
+use super::{Pixel, WIDE_CONTINUATION};
+use unicode_width::UnicodeWidthChar;
+
+/// How `Console::draw_text` lines text up inside its rect.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Align {
+    Left,
+    Centre,
+    Right,
+}
+
+const ELLIPSIS: char = '…';
+
+/// Returns how many columns `string` takes up; most CJK characters and emoji
+/// take up two, and control characters and combining marks none.
+pub fn text_width(string: &str) -> usize {
+    string.chars().map(char_width).sum()
+}
+
+pub(crate) fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+// What a backend should print for the cell at `index`: None for the right half
+// of a wide character, and a space for cells that can't be shown on their own,
+// such as half of a wide character whose other half was drawn over
+pub(crate) fn cell_glyph(screen_buffer: &[Pixel], index: usize, width: usize) -> Option<char> {
+    let x = index % width;
+    let c = screen_buffer[index].char_value;
+
+    if c == WIDE_CONTINUATION {
+        let is_covered = x > 0 && char_width(screen_buffer[index - 1].char_value) == 2;
+
+        return if is_covered { None } else { Some(' ') };
+    }
+
+    match char_width(c) {
+        1 => Some(c),
+        2 if x + 1 < width && screen_buffer[index + 1].char_value == WIDE_CONTINUATION => Some(c),
+        _ => Some(' '),
+    }
+}
+
+// Splits text into lines no wider than `width`, breaking at spaces where it can
+// and in the middle of words that don't fit on a line of their own
+pub(crate) fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    if width == 0 {
+        return lines;
+    }
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+
+        for word in paragraph.split(' ').filter(|word| !word.is_empty()) {
+            if line_width > 0 && line_width + 1 + text_width(word) > width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+
+            for c in word.chars() {
+                let c_width = char_width(c);
+
+                if line_width > 0 && line_width + c_width > width {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                }
+
+                line.push(c);
+                line_width += c_width;
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Shortens a line to fit in `width` columns with an ellipsis on the end
+pub(crate) fn ellipsize(line: &str, width: usize) -> String {
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut line = line.to_string();
+
+    while text_width(&line) + 1 > width {
+        line.pop();
+    }
+
+    let mut line = line.trim_end().to_string();
+    line.push(ELLIPSIS);
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_width() {
+        assert_eq!(text_width("Hello"), 5);
+        assert_eq!(text_width("█▓▒░"), 4);
+        assert_eq!(text_width("日本語"), 6);
+        assert_eq!(text_width("e\u{301}"), 1);
+    }
+
+    #[test]
+    fn test_cell_glyph() {
+        let cells: Vec<Pixel> = "日\0日a\0\u{301}日"
+            .chars()
+            .map(|char_value| Pixel {
+                char_value,
+                attributes: 0,
+                colours: None,
+            })
+            .collect();
+
+        let glyphs: Vec<Option<char>> = (0..cells.len())
+            .map(|index| cell_glyph(&cells, index, cells.len()))
+            .collect();
+
+        assert_eq!(
+            glyphs,
+            [
+                Some('日'),
+                None,
+                Some(' '),
+                Some('a'),
+                Some(' '),
+                Some(' '),
+                Some(' ')
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("The quick brown fox jumps", 10),
+            ["The quick", "brown fox", "jumps"]
+        );
+
+        // Words that are too long are broken, and newlines start a new line
+        assert_eq!(
+            wrap("abcdefghijkl mn\n\nop", 5),
+            ["abcde", "fghij", "kl mn", "", "op"]
+        );
+
+        // A wide character doesn't get split across lines
+        assert_eq!(wrap("日本語", 5), ["日本", "語"]);
+    }
+
+    #[test]
+    fn test_ellipsize() {
+        assert_eq!(ellipsize("brown fox", 6), "brown…");
+        assert_eq!(ellipsize("日本語", 4), "日…");
+        assert_eq!(ellipsize("ab", 1), "…");
+        assert_eq!(ellipsize("brown fox", 7), "brown…");
+    }
+}
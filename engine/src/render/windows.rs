@@ -0,0 +1,135 @@
+// Win32 console backend.
+use super::text::{cell_glyph, char_width};
+use super::{colour, FrameStats, Pixel, RenderBackend};
+use std::error::Error;
+use win32console::{
+    console::WinConsole,
+    structs::{char_info::CharInfo, coord::Coord, small_rect::SmallRect},
+};
+
+// Mark the two halves of a double-width character
+const COMMON_LVB_LEADING_BYTE: u16 = 0x0100;
+const COMMON_LVB_TRAILING_BYTE: u16 = 0x0200;
+
+pub struct WinConsoleBackend {
+    console: WinConsole,
+    buffer: Vec<CharInfo>,
+}
+
+impl WinConsoleBackend {
+    pub fn create(
+        width: u16,
+        height: u16,
+        font_width: u16,
+        font_height: u16,
+        title: &str,
+    ) -> Result<WinConsoleBackend, Box<dyn Error>> {
+        let (width, height) = (width as i16, height as i16);
+
+        let handle = WinConsole::create_console_screen_buffer()?;
+        let console = WinConsole::with_handle(handle);
+
+        WinConsole::set_active_console_screen_buffer(console.get_handle())?;
+        WinConsole::set_title(title)?;
+
+        // Set Window Size //////////
+        {
+            // In order to change the window size, you must set it to the minimum, set the SCREEN size,
+            // THEN set the window size to what you want.
+            console.set_window_info(
+                true,
+                &SmallRect {
+                    left: 0,
+                    top: 0,
+                    right: 1,
+                    bottom: 1,
+                },
+            )?;
+
+            console.set_screen_buffer_size(Coord {
+                x: width,
+                y: height,
+            })?;
+
+            console.set_window_info(
+                true,
+                &SmallRect {
+                    left: 0,
+                    top: 0,
+                    right: width - 1,
+                    bottom: height - 1,
+                },
+            )?;
+        }
+
+        // Set Font Size //////////
+        {
+            let mut new_font = console.get_font_ex(false)?;
+
+            new_font.font_size = Coord {
+                x: font_width as i16,
+                y: font_height as i16,
+            };
+
+            console.set_font_ex(new_font, false)?;
+        }
+
+        Ok(WinConsoleBackend {
+            console,
+            buffer: Vec::new(),
+        })
+    }
+}
+
+impl RenderBackend for WinConsoleBackend {
+    // WriteConsoleOutput is cheap enough that the whole frame is always written
+    fn present(
+        &mut self,
+        screen_buffer: &[Pixel],
+        _previous: Option<&[Pixel]>,
+        width: usize,
+    ) -> Result<FrameStats, Box<dyn Error>> {
+        self.buffer.clear();
+        self.buffer
+            .extend(screen_buffer.iter().enumerate().map(|(index, pixel)| {
+                // BOLD isn't a console attribute, so it mustn't reach the console
+                let attributes = pixel.attributes & !colour::BOLD;
+
+                match cell_glyph(screen_buffer, index, width) {
+                    // The right half repeats the character on its left
+                    None => CharInfo::new(
+                        screen_buffer[index - 1].char_value,
+                        attributes | COMMON_LVB_TRAILING_BYTE,
+                    ),
+                    Some(glyph) if char_width(glyph) == 2 => {
+                        CharInfo::new(glyph, attributes | COMMON_LVB_LEADING_BYTE)
+                    }
+                    Some(glyph) => CharInfo::new(glyph, attributes),
+                }
+            }));
+
+        let height = screen_buffer.len() / width;
+        let (width, height) = (width as i16, height as i16);
+
+        self.console.write_output(
+            &self.buffer,
+            Coord {
+                x: width,
+                y: height,
+            },
+            Coord::ZERO,
+            SmallRect {
+                left: 0,
+                top: 0,
+                right: width,
+                bottom: height,
+            },
+        )?;
+
+        Ok(FrameStats {
+            cells_written: self.buffer.len(),
+            // A CHAR_INFO is a UTF-16 code unit plus a u16 of attributes
+            bytes_written: self.buffer.len() * 4,
+        })
+    }
+}
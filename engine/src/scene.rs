@@ -0,0 +1,249 @@
+// Scene stack for title screens, pause overlays, gameplay and the like.
+use crate::{Context, ControlFlow, Game};
+
+/// What the SceneStack should do once a scene's update has finished.
+pub enum Transition {
+    None,
+    /// Put a new scene on top of this one.
+    Push(Box<dyn Scene>),
+    /// Remove this scene; the game exits when the last scene is popped.
+    Pop,
+    /// Swap this scene for another.
+    Replace(Box<dyn Scene>),
+    /// Leave the game altogether.
+    Exit,
+}
+
+/// One screen of a game; only the top scene of a SceneStack is updated.
+pub trait Scene {
+    /// Called when the scene is pushed onto the stack.
+    fn on_enter(&mut self, _context: &mut Context) {}
+
+    /// Called when the scene is back on top after the one above it was popped.
+    fn on_resume(&mut self, _context: &mut Context) {}
+
+    /// Handles input and updates the scene; only called for the top scene.
+    fn on_update(&mut self, context: &mut Context, delta: f64) -> Transition;
+
+    /// Draws the scene; called every frame for every visible scene, bottom first.
+    fn on_draw(&mut self, _context: &mut Context) {}
+
+    /// Called when the scene is popped or replaced, or when the game exits.
+    fn on_exit(&mut self, _context: &mut Context) {}
+
+    /// Transparent scenes are drawn over the scene beneath them instead of hiding it.
+    fn is_transparent(&self) -> bool {
+        false
+    }
+}
+
+/// Runs a stack of scenes as a Game.
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    // The first scene is held here until on_create, when it can be entered
+    initial: Option<Box<dyn Scene>>,
+}
+
+impl SceneStack {
+    pub fn new(initial: Box<dyn Scene>) -> SceneStack {
+        SceneStack {
+            scenes: Vec::new(),
+            initial: Some(initial),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    fn push(&mut self, mut scene: Box<dyn Scene>, context: &mut Context) {
+        scene.on_enter(context);
+        self.scenes.push(scene);
+    }
+
+    fn pop(&mut self, context: &mut Context) {
+        if let Some(mut scene) = self.scenes.pop() {
+            scene.on_exit(context);
+        }
+    }
+
+    fn draw(&mut self, context: &mut Context) {
+        // Start from the highest scene that isn't see-through
+        let bottom = self
+            .scenes
+            .iter()
+            .rposition(|scene| !scene.is_transparent())
+            .unwrap_or(0);
+
+        for scene in self.scenes[bottom..].iter_mut() {
+            scene.on_draw(context);
+        }
+    }
+}
+
+impl Game for SceneStack {
+    fn on_create(&mut self, context: &mut Context) {
+        if let Some(scene) = self.initial.take() {
+            self.push(scene, context);
+        }
+    }
+
+    fn on_update(&mut self, context: &mut Context, delta: f64) -> ControlFlow {
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => scene.on_update(context, delta),
+            None => return ControlFlow::Exit,
+        };
+
+        match transition {
+            Transition::None => {}
+            Transition::Push(scene) => self.push(scene, context),
+            Transition::Pop => {
+                self.pop(context);
+
+                match self.scenes.last_mut() {
+                    Some(scene) => scene.on_resume(context),
+                    None => return ControlFlow::Exit,
+                }
+            }
+            Transition::Replace(scene) => {
+                self.pop(context);
+                self.push(scene, context);
+            }
+            Transition::Exit => return ControlFlow::Exit,
+        }
+
+        self.draw(context);
+
+        ControlFlow::Continue
+    }
+
+    fn on_destroy(&mut self, context: &mut Context) {
+        while !self.scenes.is_empty() {
+            self.pop(context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{run, Config};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    // Plays back one Transition per update and logs every call it gets
+    struct ScriptedScene {
+        name: &'static str,
+        transparent: bool,
+        script: Vec<Transition>,
+        log: Log,
+    }
+
+    impl ScriptedScene {
+        fn boxed(
+            name: &'static str,
+            transparent: bool,
+            script: Vec<Transition>,
+            log: &Log,
+        ) -> Box<dyn Scene> {
+            Box::new(ScriptedScene {
+                name,
+                transparent,
+                script,
+                log: log.clone(),
+            })
+        }
+
+        fn log(&self, event: &str) {
+            self.log
+                .borrow_mut()
+                .push(format!("{} {}", self.name, event));
+        }
+    }
+
+    impl Scene for ScriptedScene {
+        fn on_enter(&mut self, _context: &mut Context) {
+            self.log("enter");
+        }
+
+        fn on_resume(&mut self, _context: &mut Context) {
+            self.log("resume");
+        }
+
+        fn on_update(&mut self, _context: &mut Context, _delta: f64) -> Transition {
+            self.log("update");
+
+            if self.script.is_empty() {
+                Transition::None
+            } else {
+                self.script.remove(0)
+            }
+        }
+
+        fn on_draw(&mut self, _context: &mut Context) {
+            self.log("draw");
+        }
+
+        fn on_exit(&mut self, _context: &mut Context) {
+            self.log("exit");
+        }
+
+        fn is_transparent(&self) -> bool {
+            self.transparent
+        }
+    }
+
+    #[test]
+    fn test_scene_transitions() {
+        let log = Log::default();
+
+        let game_over = ScriptedScene::boxed("over", false, vec![Transition::Exit], &log);
+        let pause = ScriptedScene::boxed("pause", true, vec![Transition::Pop], &log);
+        let play = ScriptedScene::boxed(
+            "play",
+            false,
+            vec![Transition::Push(pause), Transition::Replace(game_over)],
+            &log,
+        );
+        let title = ScriptedScene::boxed("title", false, vec![Transition::Push(play)], &log);
+
+        let config = Config {
+            target_fps: 1000.0,
+            headless: true,
+            ..Config::new(10, 10, "TEST")
+        };
+
+        run(SceneStack::new(title), config).unwrap();
+
+        let expected = vec![
+            "title enter",
+            "title update",
+            "play enter",
+            "play draw",
+            "play update",
+            "pause enter",
+            // The pause overlay is transparent, so play is drawn underneath it
+            "play draw",
+            "pause draw",
+            "pause update",
+            "pause exit",
+            "play resume",
+            "play draw",
+            "play update",
+            "play exit",
+            "over enter",
+            "over draw",
+            "over update",
+            "over exit",
+            "title exit",
+        ];
+
+        assert_eq!(*log.borrow(), expected);
+    }
+}
@@ -0,0 +1,599 @@
+// Tile maps: grids of tile IDs drawn with a tileset, which level designers can
+// write as ASCII art in any text editor.
+use crate::render::{self, Camera, Canvas, Colour, Console, Pixel, Rect, Sprite, Style};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Identifies a kind of tile in a Tileset.
+pub type TileId = usize;
+
+/// The tile in blank cells of a text map, and everywhere in a new Tilemap; it
+/// is only drawn, or solid, if the tileset has an entry for it.
+pub const EMPTY_TILE: TileId = 0;
+
+/// A rectangle of cells, stored row by row.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// Creates a grid with every cell set to `value`.
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    /// Returns None outside the grid.
+    pub fn get(&self, x: i32, y: i32) -> Option<&T> {
+        self.index(x, y).map(|index| &self.cells[index])
+    }
+
+    /// Does nothing outside the grid.
+    pub fn set(&mut self, x: i32, y: i32, value: T) {
+        if let Some(index) = self.index(x, y) {
+            self.cells[index] = value;
+        }
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.cells.fill(value);
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if !Rect::from_size(self.width, self.height).contains(x, y) {
+            return None;
+        }
+
+        Some(y as usize * self.width + x as usize)
+    }
+}
+
+/// What a tile looks like.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TileGraphic {
+    /// Fills the whole tile.
+    Pixel(Pixel),
+    /// Drawn from the tile's top left corner; it should be the tileset's tile
+    /// size, or it will overlap or leave gaps between its neighbours.
+    Sprite(Sprite),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Tile {
+    pub graphic: TileGraphic,
+    /// Whether the tile blocks movement; see `Tilemap::is_solid`.
+    pub solid: bool,
+}
+
+/// The tiles a Tilemap is drawn with, by ID.
+#[derive(Debug)]
+pub struct Tileset {
+    tile_width: usize,
+    tile_height: usize,
+    tiles: HashMap<TileId, Tile>,
+}
+
+impl Tileset {
+    /// Creates an empty tileset whose tiles each take up `tile_width` by
+    /// `tile_height` cells on screen.
+    pub fn new(tile_width: usize, tile_height: usize) -> Tileset {
+        Tileset {
+            tile_width,
+            tile_height,
+            tiles: HashMap::new(),
+        }
+    }
+
+    /// Adds a tile, replacing any with the same ID.
+    pub fn insert(&mut self, id: TileId, tile: Tile) {
+        self.tiles.insert(id, tile);
+    }
+
+    pub fn get(&self, id: TileId) -> Option<&Tile> {
+        self.tiles.get(&id)
+    }
+
+    /// IDs with no tile aren't solid.
+    pub fn is_solid(&self, id: TileId) -> bool {
+        self.get(id).is_some_and(|tile| tile.solid)
+    }
+
+    pub fn get_tile_width(&self) -> usize {
+        self.tile_width
+    }
+
+    pub fn get_tile_height(&self) -> usize {
+        self.tile_height
+    }
+
+    /// Draws a tile with its top left corner at (x, y) on screen; IDs with no
+    /// tile draw nothing.
+    pub fn draw_tile(&self, console: &mut Console, id: TileId, x: i32, y: i32) {
+        match self.get(id).map(|tile| &tile.graphic) {
+            Some(TileGraphic::Pixel(pixel)) => {
+                console.fill_rect(x, y, self.tile_width as i32, self.tile_height as i32, pixel)
+            }
+            Some(TileGraphic::Sprite(sprite)) => console.draw_sprite(x, y, sprite),
+            None => {}
+        }
+    }
+}
+
+/// Layers of tile IDs, all the same size, drawn with a Tileset from the
+/// bottom layer up.
+///
+/// World positions are in console cells, so tile (x, y) covers the cells from
+/// (x * tile width, y * tile height).
+#[derive(Debug)]
+pub struct Tilemap {
+    layers: Vec<Grid<TileId>>,
+    tileset: Tileset,
+    symbols: HashMap<char, TileId>,
+}
+
+impl Tilemap {
+    /// Creates a map `width` by `height` tiles in size with `layers` layers
+    /// of EMPTY_TILE.
+    pub fn new(width: usize, height: usize, layers: usize, tileset: Tileset) -> Tilemap {
+        Tilemap {
+            layers: vec![Grid::new(width, height, EMPTY_TILE); layers],
+            tileset,
+            symbols: HashMap::new(),
+        }
+    }
+
+    /// Loads a map from a text file; see `parse` for the format.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Tilemap, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+
+        Ok(Tilemap::parse(&text)?)
+    }
+
+    /// Reads a map from text made up of a `[legend]` section followed by one
+    /// or more `[layer]` sections, bottom layer first:
+    ///
+    /// ```text
+    /// ; Lines starting with ';' are comments, in layers too
+    /// [legend]
+    /// # = pixel dark_grey solid
+    /// ~ = half blue on dark_blue
+    /// $ = $ yellow
+    ///
+    /// [layer]
+    /// ######
+    /// #~~  #
+    /// ######
+    ///
+    /// [layer]
+    ///
+    ///     $
+    /// ```
+    ///
+    /// Each legend entry gives a symbol, the character its tile is drawn with
+    /// (a single character, or one of `space`, `pixel`, `three_quarters`,
+    /// `half` and `quarter`), a foreground colour, optionally `on` and a
+    /// background colour, and `solid` if it blocks movement. Colours are written
+    /// as in markup, such as `dark_red` or `#ff8000`. Symbols get tile IDs from 1 in the
+    /// order they are listed, which `get_symbol_id` looks up. Since `;` starts
+    /// comments it can't be a symbol.
+    ///
+    /// Spaces in a layer are EMPTY_TILE, and short or missing rows are filled
+    /// out with it, since editors tend to trim trailing spaces; the map is as
+    /// big as its widest row and tallest layer.
+    pub fn parse(text: &str) -> Result<Tilemap, TilemapError> {
+        let mut tileset = Tileset::new(1, 1);
+        let mut symbols = HashMap::new();
+        let mut layers: Vec<Vec<Vec<TileId>>> = Vec::new();
+        let mut section = Section::None;
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |message: String| TilemapError {
+                line: line_number,
+                message,
+            };
+
+            match line.trim() {
+                "[legend]" if layers.is_empty() => {
+                    section = Section::Legend;
+                    continue;
+                }
+                "[legend]" => return Err(error("the legend must come before the layers".into())),
+                "[layer]" => {
+                    section = Section::Layer;
+                    layers.push(Vec::new());
+                    continue;
+                }
+                _ => {}
+            }
+
+            match section {
+                _ if line.starts_with(';') => {}
+                Section::Layer => {
+                    let row =
+                        line.chars()
+                            .map(|symbol| match symbol {
+                                ' ' => Ok(EMPTY_TILE),
+                                ';' => Err(error(
+                                    "';' starts comments, so it can't be a symbol".into(),
+                                )),
+                                _ => symbols.get(&symbol).copied().ok_or_else(|| {
+                                    error(format!("'{}' is not in the legend", symbol))
+                                }),
+                            })
+                            .collect::<Result<Vec<TileId>, TilemapError>>()?;
+
+                    layers.last_mut().unwrap().push(row);
+                }
+                _ if line.trim().is_empty() => {}
+                Section::Legend => {
+                    let (symbol, tile) = parse_legend_entry(line).map_err(error)?;
+
+                    if symbols.contains_key(&symbol) {
+                        return Err(error(format!("'{}' is already in the legend", symbol)));
+                    }
+
+                    let id = symbols.len() + 1;
+
+                    symbols.insert(symbol, id);
+                    tileset.insert(id, tile);
+                }
+                Section::None => {
+                    return Err(error("expected [legend] or [layer]".into()));
+                }
+            }
+        }
+
+        if layers.is_empty() {
+            return Err(TilemapError {
+                line: text.lines().count(),
+                message: "the map has no layers".into(),
+            });
+        }
+
+        // Blank lines between layers belong to neither
+        for rows in layers.iter_mut() {
+            while rows.last().is_some_and(|row| row.is_empty()) {
+                rows.pop();
+            }
+        }
+
+        let width = layers.iter().flatten().map(Vec::len).max().unwrap_or(0);
+        let height = layers.iter().map(Vec::len).max().unwrap_or(0);
+
+        let mut map = Tilemap::new(width, height, layers.len(), tileset);
+        map.symbols = symbols;
+
+        for (layer, rows) in layers.iter().enumerate() {
+            for (y, row) in rows.iter().enumerate() {
+                for (x, &id) in row.iter().enumerate() {
+                    map.set_tile(layer, x as i32, y as i32, id);
+                }
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Returns None outside the map; panics if there is no such layer.
+    pub fn get_tile(&self, layer: usize, x: i32, y: i32) -> Option<TileId> {
+        self.layers[layer].get(x, y).copied()
+    }
+
+    /// Does nothing outside the map; panics if there is no such layer.
+    pub fn set_tile(&mut self, layer: usize, x: i32, y: i32, id: TileId) {
+        self.layers[layer].set(x, y, id);
+    }
+
+    pub fn get_layer(&self, layer: usize) -> &Grid<TileId> {
+        &self.layers[layer]
+    }
+
+    pub fn get_layer_mut(&mut self, layer: usize) -> &mut Grid<TileId> {
+        &mut self.layers[layer]
+    }
+
+    pub fn get_layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// The map's width in tiles.
+    pub fn get_width(&self) -> usize {
+        self.layers.first().map_or(0, Grid::get_width)
+    }
+
+    /// The map's height in tiles.
+    pub fn get_height(&self) -> usize {
+        self.layers.first().map_or(0, Grid::get_height)
+    }
+
+    pub fn get_tileset(&self) -> &Tileset {
+        &self.tileset
+    }
+
+    pub fn get_tileset_mut(&mut self) -> &mut Tileset {
+        &mut self.tileset
+    }
+
+    /// The ID the map's legend gave to a symbol, for maps that were loaded.
+    pub fn get_symbol_id(&self, symbol: char) -> Option<TileId> {
+        self.symbols.get(&symbol).copied()
+    }
+
+    /// Whether the tile at (x, y) is solid on any layer; nothing outside the
+    /// map is.
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        self.layers
+            .iter()
+            .filter_map(|layer| layer.get(x, y))
+            .any(|&id| self.tileset.is_solid(id))
+    }
+
+    /// The area of the world the map covers, for `Camera::set_bounds`.
+    pub fn get_bounds(&self) -> Rect {
+        Rect::new(
+            0,
+            0,
+            (self.get_width() * self.tileset.tile_width) as i32,
+            (self.get_height() * self.tileset.tile_height) as i32,
+        )
+    }
+
+    /// Draws the part of the map the camera can see into its viewport, lower
+    /// layers first; tiles out of view are skipped.
+    pub fn draw(&self, console: &mut Console, camera: &Camera) {
+        let tile_width = self.tileset.tile_width.max(1) as i32;
+        let tile_height = self.tileset.tile_height.max(1) as i32;
+        let viewport = camera.get_viewport();
+
+        if self.get_width() == 0 || self.get_height() == 0 || viewport.width <= 0 {
+            return;
+        }
+
+        // The tiles under the viewport's corners
+        let (left, top) = camera.screen_to_world(viewport.x, viewport.y);
+        let (right, bottom) = camera.screen_to_world(
+            viewport.x + viewport.width - 1,
+            viewport.y + viewport.height - 1,
+        );
+
+        let first_x = left.div_euclid(tile_width).max(0);
+        let first_y = top.div_euclid(tile_height).max(0);
+        let last_x = right
+            .div_euclid(tile_width)
+            .min(self.get_width() as i32 - 1);
+        let last_y = bottom
+            .div_euclid(tile_height)
+            .min(self.get_height() as i32 - 1);
+
+        console.push_clip(viewport);
+
+        for layer in &self.layers {
+            for y in first_y..=last_y {
+                for x in first_x..=last_x {
+                    let id = *layer.get(x, y).unwrap();
+                    let (screen_x, screen_y) =
+                        camera.world_to_screen(x * tile_width, y * tile_height);
+
+                    self.tileset.draw_tile(console, id, screen_x, screen_y);
+                }
+            }
+        }
+
+        console.pop_clip();
+    }
+}
+
+/// Why a text map couldn't be read; `line` counts from 1.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TilemapError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TilemapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for TilemapError {}
+
+enum Section {
+    None,
+    Legend,
+    Layer,
+}
+
+// Reads a legend entry such as "# = pixel dark_grey on black solid"
+fn parse_legend_entry(line: &str) -> Result<(char, Tile), String> {
+    let mut chars = line.chars();
+    let symbol = chars.next().unwrap();
+
+    let definition = chars
+        .as_str()
+        .trim_start()
+        .strip_prefix('=')
+        .ok_or_else(|| format!("expected '=' after '{}'", symbol))?;
+    let mut words = definition.split_whitespace();
+
+    let glyph = match words.next() {
+        Some("space") => ' ',
+        Some("pixel") => render::PIXEL,
+        Some("three_quarters") => render::PIXEL_THREE_QUARTERS,
+        Some("half") => render::PIXEL_HALF,
+        Some("quarter") => render::PIXEL_QUARTER,
+        Some(word) if word.chars().count() == 1 => word.chars().next().unwrap(),
+        Some(word) => return Err(format!("unknown glyph '{}'", word)),
+        None => return Err(format!("'{}' has no glyph", symbol)),
+    };
+
+    let colour_by_name = |name: Option<&str>| {
+        let name = name.ok_or_else(|| format!("'{}' is missing a colour", symbol))?;
+
+        name.parse::<Colour>().map_err(|error| error.to_string())
+    };
+
+    let mut style = Style::fg(colour_by_name(words.next())?);
+    let mut solid = false;
+
+    while let Some(word) = words.next() {
+        match word {
+            "on" => style.bg = colour_by_name(words.next())?,
+            "solid" => solid = true,
+            _ => return Err(format!("unexpected '{}'", word)),
+        }
+    }
+
+    let tile = Tile {
+        graphic: TileGraphic::Pixel(Pixel::new(glyph, style)),
+        solid,
+    };
+
+    Ok((symbol, tile))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{colour, HeadlessBackend, PIXEL_EMPTY};
+
+    const MAP: &str = "; A small room
+[legend]
+# = pixel dark_grey solid
+~ = half blue on dark_blue
+$ = $ yellow
+
+[layer]
+######
+; Water
+#~~  #
+######
+
+[layer]
+
+    $
+";
+
+    #[test]
+    fn test_parse() {
+        let map = Tilemap::parse(MAP).unwrap();
+
+        assert_eq!((map.get_width(), map.get_height()), (6, 3));
+        assert_eq!(map.get_layer_count(), 2);
+
+        let wall = map.get_symbol_id('#').unwrap();
+        let water = map.get_symbol_id('~').unwrap();
+        let coin = map.get_symbol_id('$').unwrap();
+
+        assert_eq!((wall, water, coin), (1, 2, 3));
+        assert_eq!(map.get_tile(0, 1, 1), Some(water));
+        assert_eq!(map.get_tile(0, 3, 1), Some(EMPTY_TILE));
+        assert_eq!(map.get_tile(1, 4, 1), Some(coin));
+        // Rows left short, and the missing last row, are empty
+        assert_eq!(map.get_tile(1, 5, 2), Some(EMPTY_TILE));
+        assert_eq!(map.get_tile(0, 6, 0), None);
+
+        assert_eq!(
+            map.get_tileset().get(water),
+            Some(&Tile {
+                graphic: TileGraphic::Pixel(Pixel {
+                    char_value: render::PIXEL_HALF,
+                    attributes: colour::FG_BLUE | colour::BG_DARK_BLUE,
+                    colours: None,
+                }),
+                solid: false,
+            })
+        );
+
+        assert!(map.is_solid(0, 0));
+        assert!(!map.is_solid(1, 1));
+        assert!(!map.is_solid(-1, 0));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text: &str| Tilemap::parse(text).unwrap_err().to_string();
+
+        assert_eq!(
+            error("[legend]\n# pixel white"),
+            "line 2: expected '=' after '#'"
+        );
+        assert_eq!(
+            error("[legend]\n# = pixel purple"),
+            "line 2: unknown colour 'purple'"
+        );
+        assert_eq!(
+            error("[legend]\n# = pixel white\n# = O red"),
+            "line 3: '#' is already in the legend"
+        );
+        assert_eq!(
+            error("[legend]\n# = pixel white\n[layer]\n#x#"),
+            "line 4: 'x' is not in the legend"
+        );
+        assert_eq!(
+            error("[legend]\n# = pixel white"),
+            "line 2: the map has no layers"
+        );
+        assert_eq!(
+            error("[legend]\n# = pixel white\n[layer]\n#;#"),
+            "line 4: ';' starts comments, so it can't be a symbol"
+        );
+        assert_eq!(error("#"), "line 1: expected [legend] or [layer]");
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut map = Tilemap::parse(MAP).unwrap();
+        let mut console = Console::with_backend(4, 3, Box::new(HeadlessBackend::new()));
+        let mut camera = Camera::new(Rect::new(1, 1, 3, 2));
+        camera.set_position(3.0, 0.0);
+
+        // Swap the coin for a sprite
+        let coin = Pixel {
+            char_value: 'o',
+            attributes: colour::FG_YELLOW,
+            colours: None,
+        };
+        let mut sprite = Sprite::new(1, 1);
+        sprite.set_pixel(0, 0, &coin);
+        let id = map.get_symbol_id('$').unwrap();
+        map.get_tileset_mut().insert(
+            id,
+            Tile {
+                graphic: TileGraphic::Sprite(sprite),
+                solid: false,
+            },
+        );
+
+        map.draw(&mut console, &camera);
+
+        let wall = map.get_tileset().get(1).unwrap().graphic.clone();
+        let glyph = |x, y| TileGraphic::Pixel(console.get_pixel(x, y));
+
+        // Only the viewport is drawn, showing world cells (3, 0) to (5, 1)
+        assert_eq!(console.get_pixel(0, 0), PIXEL_EMPTY);
+        assert_eq!(glyph(1, 1), wall);
+        assert_eq!(glyph(3, 1), wall);
+        assert_eq!(console.get_pixel(1, 2), PIXEL_EMPTY);
+        assert_eq!(console.get_pixel(2, 2), coin);
+        assert_eq!(console.get_clip(), Rect::new(0, 0, 4, 3));
+    }
+}
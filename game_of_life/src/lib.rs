@@ -1,72 +1,78 @@
 use engine::{
-    input::{Key, Keyboard},
-    render::{self, colour, Console, Pixel},
+    input::Key,
+    render::{Canvas, Colour, HalfBlockCanvas},
+    Config, Context, ControlFlow, Game, RunOptions,
 };
 
 const SCREEN_WIDTH: usize = 80;
 const SCREEN_HEIGHT: usize = 45;
 
-const ASSETS: [Pixel; 2] = [assets::DEAD_CELL, assets::LIVE_CELL];
+// Each console cell holds two cells of the board, one above the other
+const BOARD_WIDTH: usize = SCREEN_WIDTH;
+const BOARD_HEIGHT: usize = SCREEN_HEIGHT * 2;
 
-const TICK_INTERVAL: u64 = 15; // ms
+const ASSETS: [Colour; 2] = [assets::DEAD_CELL, assets::LIVE_CELL];
 
-pub fn run() {
-    let mut console = Console::create(
-        SCREEN_WIDTH as u16,
-        SCREEN_HEIGHT as u16,
-        8,
-        8,
-        "CONWAY'S GAME OF LIFE",
-    )
-    .expect("Failed to create Console");
-    let mut keyboard = Keyboard::create(vec![Key::ESCAPE, Key::CHAR_Q]);
+const TARGET_FPS: f64 = 60.0;
 
-    let mut board = [0; SCREEN_WIDTH * SCREEN_HEIGHT];
+struct GameOfLife {
+    board: [usize; BOARD_WIDTH * BOARD_HEIGHT],
+    canvas: HalfBlockCanvas,
+}
 
-    board[2 * SCREEN_WIDTH + 1] = 1;
-    board[2 * SCREEN_WIDTH + 2] = 1;
-    board[2 * SCREEN_WIDTH + 3] = 1;
-    board[1 * SCREEN_WIDTH + 3] = 1;
-    board[0 * SCREEN_WIDTH + 2] = 1;
+impl GameOfLife {
+    fn new() -> GameOfLife {
+        let mut board = [0; BOARD_WIDTH * BOARD_HEIGHT];
 
-    loop {
-        // TICK //////////
-        std::thread::sleep(std::time::Duration::from_millis(TICK_INTERVAL));
+        // Glider
+        for (x, y) in [(1, 2), (2, 2), (3, 2), (3, 1), (2, 0)] {
+            board[y * BOARD_WIDTH + x] = 1;
+        }
 
-        // INPUT //////////
-        keyboard.update_key_states();
+        GameOfLife {
+            board,
+            canvas: HalfBlockCanvas::new(BOARD_WIDTH, BOARD_HEIGHT),
+        }
+    }
+}
 
-        if keyboard.get_key_state(Key::ESCAPE).is_pressed() {
-            break;
+impl Game for GameOfLife {
+    fn on_update(&mut self, context: &mut Context, _delta: f64) -> ControlFlow {
+        let console = &mut context.console;
+
+        // INPUT //////////
+        if context.keyboard.get_key_state(Key::ESCAPE).is_pressed() {
+            return ControlFlow::Exit;
         }
+
         // ALGORITHM //////////
         // Create a new board
-        let mut new_board = [0; SCREEN_WIDTH * SCREEN_HEIGHT];
+        let mut new_board = [0; BOARD_WIDTH * BOARD_HEIGHT];
 
         // Alter board according to algo
-        for x in 1..SCREEN_WIDTH - 1 {
-            for y in 1..SCREEN_HEIGHT - 1 {
-                let current_cell_index = y * SCREEN_WIDTH + x;
+        for x in 1..BOARD_WIDTH - 1 {
+            for y in 1..BOARD_HEIGHT - 1 {
+                let current_cell_index = y * BOARD_WIDTH + x;
                 let mut neighbour_cells_indexes = [0; 8];
 
                 let mut live_neighbour_count = 0;
 
-                neighbour_cells_indexes[0] = (y - 1) * SCREEN_WIDTH + (x - 1); // Top Left
-                neighbour_cells_indexes[1] = (y - 1) * SCREEN_WIDTH + x; // Top Middle
-                neighbour_cells_indexes[2] = (y - 1) * SCREEN_WIDTH + (x + 1); // Top Right
-                neighbour_cells_indexes[3] = y * SCREEN_WIDTH + (x - 1); // Middle Left
-                neighbour_cells_indexes[4] = y * SCREEN_WIDTH + (x + 1); // Middle Right
-                neighbour_cells_indexes[5] = (y + 1) * SCREEN_WIDTH + (x - 1); // Bottom Left
-                neighbour_cells_indexes[6] = (y + 1) * SCREEN_WIDTH + x; // Bottom Middle
-                neighbour_cells_indexes[7] = (y + 1) * SCREEN_WIDTH + (x + 1); // Bottom Right
+                neighbour_cells_indexes[0] = (y - 1) * BOARD_WIDTH + (x - 1); // Top Left
+                neighbour_cells_indexes[1] = (y - 1) * BOARD_WIDTH + x; // Top Middle
+                neighbour_cells_indexes[2] = (y - 1) * BOARD_WIDTH + (x + 1); // Top Right
+                neighbour_cells_indexes[3] = y * BOARD_WIDTH + (x - 1); // Middle Left
+                neighbour_cells_indexes[4] = y * BOARD_WIDTH + (x + 1); // Middle Right
+                neighbour_cells_indexes[5] = (y + 1) * BOARD_WIDTH + (x - 1); // Bottom Left
+                neighbour_cells_indexes[6] = (y + 1) * BOARD_WIDTH + x; // Bottom Middle
+                neighbour_cells_indexes[7] = (y + 1) * BOARD_WIDTH + (x + 1); // Bottom Right
 
                 for i in neighbour_cells_indexes {
-                    if board[i] == 1 {
+                    if self.board[i] == 1 {
                         live_neighbour_count += 1;
                     }
                 }
 
-                if board[current_cell_index] == 1 {
+                if self.board[current_cell_index] == 1 {
                     if live_neighbour_count == 2 || live_neighbour_count == 3 {
                         new_board[current_cell_index] = 1;
                     } else {
@@ -81,33 +87,46 @@ pub fn run() {
         }
 
         // Set the board to the new board
-        board = new_board;
+        self.board = new_board;
 
         // RENDER //////////
         // Render board
-        for x in 0..SCREEN_WIDTH {
-            for y in 0..SCREEN_HEIGHT {
-                let index = y * SCREEN_WIDTH + x;
-                let cell_state = board[index];
+        for x in 0..BOARD_WIDTH {
+            for y in 0..BOARD_HEIGHT {
+                let index = y * BOARD_WIDTH + x;
+                let cell_state = self.board[index];
 
-                console.draw_pixel(x, y, &ASSETS[cell_state]);
+                self.canvas.plot(x as i32, y as i32, &ASSETS[cell_state]);
             }
         }
 
-        console.update_screen().expect("Failed to update screen");
+        self.canvas.draw_to(console, 0, 0);
+
+        ControlFlow::Continue
     }
 }
 
+pub fn run(options: RunOptions) {
+    let config = Config {
+        // Half of a cell is then square
+        font_width: 8,
+        font_height: 16,
+        keys: vec![Key::ESCAPE, Key::CHAR_Q],
+        target_fps: TARGET_FPS,
+        ..Config::new(
+            SCREEN_WIDTH as u16,
+            SCREEN_HEIGHT as u16,
+            "CONWAY'S GAME OF LIFE",
+        )
+    };
+
+    engine::run(GameOfLife::new(), options.apply(config)).expect("Failed to run GAME OF LIFE");
+}
+
 mod assets {
-    use super::{colour, render, Pixel};
+    use super::Colour;
 
-    pub const LIVE_CELL: Pixel = Pixel {
-        char_value: render::PIXEL,
-        attributes: colour::FG_WHITE,
-    };
+    pub const LIVE_CELL: Colour = Colour::WHITE;
 
-    pub const DEAD_CELL: Pixel = Pixel {
-        char_value: render::PIXEL,
-        attributes: colour::FG_BLACK,
-    };
+    pub const DEAD_CELL: Colour = Colour::BLACK;
 }
@@ -1,19 +1,43 @@
-use game_of_life;
-use tetris;
+use engine::RunOptions;
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let mut options = RunOptions::default();
+    let mut args = Vec::new();
+    let mut all_args = std::env::args().skip(1);
 
-    if args.len() < 2 {
+    // Flags can go anywhere after the game name
+    while let Some(arg) = all_args.next() {
+        match arg.as_str() {
+            "--headless" => options.headless = true,
+            "--record" => match all_args.next() {
+                Some(path) => options.recording = Some(path.into()),
+                None => {
+                    println!("You need to specify a .cast file to record to.");
+                    std::process::exit(0);
+                }
+            },
+            _ => args.push(arg),
+        }
+    }
+
+    if args.is_empty() {
         println!("You need to specify a game to run.");
         std::process::exit(0);
     }
 
-    let game_name = args[1].as_str();
+    let game_name = args[0].as_str();
 
     match game_name.to_lowercase().as_str() {
-        "gol" => game_of_life::run(),
-        "tetris" => tetris::run(),
+        "gol" => game_of_life::run(options),
+        "tetris" => tetris::run(options),
+        "editor" => match args.get(1) {
+            Some(path) => {
+                if let Err(error) = sprite_editor::run(path, options) {
+                    println!("Could not open {}: {}", path, error);
+                }
+            }
+            None => println!("You need to specify a sprite file to edit."),
+        },
         _ => println!("Not a valid game name."),
     }
 }
@@ -0,0 +1,537 @@
+use engine::{
+    input::Key,
+    render::{self, colour, Canvas, Colour, Console, Pixel, Rect, Sprite, Style},
+    Config, Context, ControlFlow, Game, RunOptions,
+};
+use std::error::Error;
+use std::path::Path;
+
+const SCREEN_WIDTH: u16 = 80;
+const SCREEN_HEIGHT: u16 = 30;
+
+const TARGET_FPS: f64 = 30.0;
+
+// The part of the screen the sprite is shown in; bigger sprites scroll
+const CANVAS_X: i32 = 1;
+const CANVAS_Y: i32 = 2;
+const CANVAS_WIDTH: i32 = 48;
+const CANVAS_HEIGHT: i32 = 26;
+
+const PANEL_X: i32 = 52;
+
+const NEW_SPRITE_SIZE: usize = 16;
+const MAX_SPRITE_SIZE: usize = 256;
+
+// Snapshots kept for undo; sprites are small enough to just copy
+const MAX_UNDO: usize = 100;
+
+const GLYPHS: [char; 4] = [
+    render::PIXEL,
+    render::PIXEL_THREE_QUARTERS,
+    render::PIXEL_HALF,
+    render::PIXEL_QUARTER,
+];
+
+const GLYPH_KEYS: [Key; 4] = [Key::CHAR_1, Key::CHAR_2, Key::CHAR_3, Key::CHAR_4];
+
+struct SpriteEditor {
+    path: String,
+    sprite: Sprite,
+    cursor_x: i32,
+    cursor_y: i32,
+    // Indexes into the 16 console colours and GLYPHS
+    foreground: u8,
+    background: u8,
+    glyph: usize,
+    undo: Vec<Sprite>,
+    redo: Vec<Sprite>,
+    unsaved: bool,
+    // Set by the first ESC while there are unsaved changes
+    confirm_quit: bool,
+    status: String,
+}
+
+impl SpriteEditor {
+    fn new(path: &str, mut sprite: Sprite, status: &str) -> SpriteEditor {
+        // The cursor needs at least one cell to be on
+        if sprite.get_width() == 0 || sprite.get_height() == 0 {
+            sprite.resize(sprite.get_width().max(1), sprite.get_height().max(1));
+        }
+
+        SpriteEditor {
+            path: path.to_string(),
+            sprite,
+            cursor_x: 0,
+            cursor_y: 0,
+            foreground: 15,
+            background: 0,
+            glyph: 0,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            unsaved: false,
+            confirm_quit: false,
+            status: status.to_string(),
+        }
+    }
+
+    fn brush(&self) -> Pixel {
+        let style = Style::new(
+            Colour::Indexed(self.foreground),
+            Colour::Indexed(self.background),
+        );
+
+        Pixel::new(GLYPHS[self.glyph], style)
+    }
+
+    // Applies a change to the sprite as one undo step; changes that leave the
+    // sprite as it was aren't recorded
+    fn edit<F: FnOnce(&mut Sprite)>(&mut self, change: F) {
+        let before = self.sprite.clone();
+
+        change(&mut self.sprite);
+
+        if self.sprite == before {
+            return;
+        }
+
+        if self.undo.len() == MAX_UNDO {
+            self.undo.remove(0);
+        }
+
+        self.undo.push(before);
+        self.redo.clear();
+        self.unsaved = true;
+    }
+
+    fn undo(&mut self) {
+        if let Some(sprite) = self.undo.pop() {
+            self.redo.push(std::mem::replace(&mut self.sprite, sprite));
+            self.unsaved = true;
+            self.move_cursor(0, 0);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(sprite) = self.redo.pop() {
+            self.undo.push(std::mem::replace(&mut self.sprite, sprite));
+            self.unsaved = true;
+            self.move_cursor(0, 0);
+        }
+    }
+
+    // Moves the cursor, keeping it on the sprite
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let (width, height) = (
+            self.sprite.get_width() as i32,
+            self.sprite.get_height() as i32,
+        );
+
+        self.cursor_x = (self.cursor_x + dx).clamp(0, (width - 1).max(0));
+        self.cursor_y = (self.cursor_y + dy).clamp(0, (height - 1).max(0));
+    }
+
+    fn resize(&mut self, dx: i32, dy: i32) {
+        let width = (self.sprite.get_width() as i32 + dx).clamp(1, MAX_SPRITE_SIZE as i32);
+        let height = (self.sprite.get_height() as i32 + dy).clamp(1, MAX_SPRITE_SIZE as i32);
+
+        self.edit(|sprite| sprite.resize(width as usize, height as usize));
+        self.move_cursor(0, 0);
+    }
+
+    fn save(&mut self) {
+        match self.sprite.save(&self.path) {
+            Ok(()) => {
+                self.unsaved = false;
+                self.status = "Saved".to_string();
+            }
+            Err(error) => self.status = format!("Could not save: {}", error),
+        }
+    }
+
+    fn handle_input(&mut self, context: &Context) -> ControlFlow {
+        let keyboard = &context.keyboard;
+        let pressed = |key| keyboard.get_key_state(key).is_pressed();
+
+        if pressed(Key::ESCAPE) {
+            if !self.unsaved || self.confirm_quit {
+                return ControlFlow::Exit;
+            }
+
+            self.confirm_quit = true;
+            self.status = "Unsaved changes; ESC again to quit".to_string();
+
+            return ControlFlow::Continue;
+        }
+
+        let (mut dx, mut dy) = (0, 0);
+
+        if pressed(Key::LEFT) {
+            dx -= 1;
+        }
+        if pressed(Key::RIGHT) {
+            dx += 1;
+        }
+        if pressed(Key::UP) {
+            dy -= 1;
+        }
+        if pressed(Key::DOWN) {
+            dy += 1;
+        }
+
+        if keyboard.get_key_state(Key::SHIFT).is_pressed_or_held() {
+            if dx != 0 || dy != 0 {
+                self.resize(dx, dy);
+            }
+        } else {
+            self.move_cursor(dx, dy);
+        }
+
+        let (x, y) = (self.cursor_x, self.cursor_y);
+        let brush = self.brush();
+
+        // Painting follows the cursor for as long as SPACE is down
+        if keyboard.get_key_state(Key::SPACE).is_pressed_or_held() {
+            self.edit(|sprite| sprite.set_pixel(x, y, &brush));
+        }
+        if keyboard.get_key_state(Key::DELETE).is_pressed_or_held()
+            || keyboard.get_key_state(Key::BACK).is_pressed_or_held()
+        {
+            self.edit(|sprite| sprite.set_pixel(x, y, &render::PIXEL_EMPTY));
+        }
+        if pressed(Key::CHAR_F) {
+            self.edit(|sprite| flood_fill(sprite, x, y, &brush));
+        }
+
+        if pressed(Key::CHAR_Z) {
+            self.undo();
+        }
+        if pressed(Key::CHAR_Y) {
+            self.redo();
+        }
+
+        // Palette
+        if pressed(Key::CHAR_C) {
+            self.foreground = (self.foreground + 15) % 16;
+        }
+        if pressed(Key::CHAR_V) {
+            self.foreground = (self.foreground + 1) % 16;
+        }
+        if pressed(Key::CHAR_B) {
+            self.background = (self.background + 15) % 16;
+        }
+        if pressed(Key::CHAR_N) {
+            self.background = (self.background + 1) % 16;
+        }
+
+        for (glyph, key) in GLYPH_KEYS.iter().enumerate() {
+            if pressed(*key) {
+                self.glyph = glyph;
+            }
+        }
+
+        if pressed(Key::CHAR_S) {
+            self.save();
+        }
+
+        ControlFlow::Continue
+    }
+
+    fn draw(&self, console: &mut Console, elapsed: f64) {
+        console.fill(0, 0, &render::PIXEL_EMPTY);
+
+        // STATUS //////////
+        let name = Path::new(&self.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let header = format!(
+            "{}{}  {}x{}  ({}, {})  {}",
+            name,
+            if self.unsaved { "*" } else { "" },
+            self.sprite.get_width(),
+            self.sprite.get_height(),
+            self.cursor_x,
+            self.cursor_y,
+            self.status,
+        );
+
+        console.draw_string(1, 0, &header, Style::fg(Colour::WHITE));
+
+        // CANVAS //////////
+        console.draw_rect(
+            CANVAS_X - 1,
+            CANVAS_Y - 1,
+            CANVAS_WIDTH + 2,
+            CANVAS_HEIGHT + 2,
+            &assets::BORDER,
+        );
+
+        // Scroll just far enough to keep the cursor in view
+        let view_x = (self.cursor_x - CANVAS_WIDTH + 1).max(0);
+        let view_y = (self.cursor_y - CANVAS_HEIGHT + 1).max(0);
+
+        let visible_width = (self.sprite.get_width() as i32 - view_x).min(CANVAS_WIDTH);
+        let visible_height = (self.sprite.get_height() as i32 - view_y).min(CANVAS_HEIGHT);
+
+        // A dotted backdrop shows which cells are transparent
+        console.fill_rect(
+            CANVAS_X,
+            CANVAS_Y,
+            visible_width,
+            visible_height,
+            &assets::TRANSPARENT,
+        );
+        console.draw_partial_sprite(
+            CANVAS_X,
+            CANVAS_Y,
+            &self.sprite,
+            Rect::new(view_x, view_y, CANVAS_WIDTH, CANVAS_HEIGHT),
+        );
+
+        if elapsed.fract() < 0.6 {
+            console.draw_pixel(
+                CANVAS_X + self.cursor_x - view_x,
+                CANVAS_Y + self.cursor_y - view_y,
+                &assets::CURSOR,
+            );
+        }
+
+        // PALETTE //////////
+        console.draw_string(PANEL_X, 2, "FOREGROUND   C/V", Style::fg(Colour::WHITE));
+        console.draw_string(PANEL_X, 6, "BACKGROUND   B/N", Style::fg(Colour::WHITE));
+
+        for colour in 0..16 {
+            let x = PANEL_X + colour as i32;
+            let swatch = Pixel::new(render::PIXEL, Style::fg(Colour::Indexed(colour)));
+
+            console.draw_pixel(x, 3, &swatch);
+            console.draw_pixel(x, 7, &swatch);
+        }
+
+        console.draw_pixel(PANEL_X + self.foreground as i32, 4, &assets::MARKER);
+        console.draw_pixel(PANEL_X + self.background as i32, 8, &assets::MARKER);
+
+        console.draw_string(PANEL_X, 10, "GLYPH        1-4", Style::fg(Colour::WHITE));
+
+        for (index, glyph) in GLYPHS.iter().enumerate() {
+            let x = PANEL_X + index as i32 * 2;
+            let pixel = Pixel {
+                char_value: *glyph,
+                ..self.brush()
+            };
+
+            console.draw_pixel(x, 11, &pixel);
+
+            if index == self.glyph {
+                console.draw_pixel(x, 12, &assets::MARKER);
+            }
+        }
+
+        console.draw_string(PANEL_X, 14, "BRUSH", Style::fg(Colour::WHITE));
+        console.fill_rect(PANEL_X + 6, 14, 3, 1, &self.brush());
+
+        // HELP //////////
+        let help = [
+            "ARROWS        move",
+            "SHIFT+ARROWS  resize",
+            "SPACE         paint",
+            "DEL           erase",
+            "F             fill",
+            "Z / Y         undo / redo",
+            "S             save",
+            "ESC           quit",
+        ];
+
+        for (line, text) in help.iter().enumerate() {
+            console.draw_string(PANEL_X, 17 + line as i32, text, Style::fg(Colour::GREY));
+        }
+    }
+}
+
+impl Game for SpriteEditor {
+    fn on_update(&mut self, context: &mut Context, _delta: f64) -> ControlFlow {
+        if self.handle_input(context) == ControlFlow::Exit {
+            return ControlFlow::Exit;
+        }
+
+        let elapsed = context.clock.get_elapsed();
+
+        self.draw(&mut context.console, elapsed);
+
+        ControlFlow::Continue
+    }
+}
+
+// Replaces the area of matching Pixels around (x, y), not counting diagonals
+fn flood_fill(sprite: &mut Sprite, x: i32, y: i32, pixel: &Pixel) {
+    let bounds = Rect::from_size(sprite.get_width(), sprite.get_height());
+
+    if !bounds.contains(x, y) {
+        return;
+    }
+
+    let target = sprite.get_pixel(x as usize, y as usize);
+
+    if target == *pixel {
+        return;
+    }
+
+    let mut stack = vec![(x, y)];
+
+    while let Some((x, y)) = stack.pop() {
+        if !bounds.contains(x, y) {
+            continue;
+        }
+
+        if sprite.get_pixel(x as usize, y as usize) != target {
+            continue;
+        }
+
+        sprite.set_pixel(x, y, pixel);
+
+        stack.extend([(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]);
+    }
+}
+
+/// Opens the sprite at `path` for editing, or starts a new one if there is no
+/// file there yet. Returns an error if the file there can't be read as a sprite.
+pub fn run(path: &str, options: RunOptions) -> Result<(), Box<dyn Error>> {
+    let editor = if Path::new(path).exists() {
+        let sprite = Sprite::load(path)?;
+
+        SpriteEditor::new(path, sprite, "")
+    } else {
+        let sprite = Sprite::new(NEW_SPRITE_SIZE, NEW_SPRITE_SIZE);
+
+        SpriteEditor::new(path, sprite, "New sprite")
+    };
+
+    let mut keys = vec![
+        Key::ESCAPE,
+        Key::UP,
+        Key::DOWN,
+        Key::LEFT,
+        Key::RIGHT,
+        Key::SHIFT,
+        Key::SPACE,
+        Key::DELETE,
+        Key::BACK,
+        Key::CHAR_F,
+        Key::CHAR_Z,
+        Key::CHAR_Y,
+        Key::CHAR_C,
+        Key::CHAR_V,
+        Key::CHAR_B,
+        Key::CHAR_N,
+        Key::CHAR_S,
+    ];
+    keys.extend(GLYPH_KEYS);
+
+    let config = Config {
+        keys,
+        target_fps: TARGET_FPS,
+        ..Config::new(SCREEN_WIDTH, SCREEN_HEIGHT, "SPRITE EDITOR")
+    };
+
+    engine::run(editor, options.apply(config)).expect("Could not run the SPRITE EDITOR");
+
+    Ok(())
+}
+
+mod assets {
+    use super::{colour, render, Pixel};
+
+    pub const BORDER: Pixel = Pixel {
+        char_value: render::PIXEL_QUARTER,
+        attributes: colour::FG_DARK_GREY,
+        colours: None,
+    };
+    pub const TRANSPARENT: Pixel = Pixel {
+        char_value: '·',
+        attributes: colour::FG_DARK_GREY,
+        colours: None,
+    };
+    pub const CURSOR: Pixel = Pixel {
+        char_value: '+',
+        attributes: colour::FG_BLACK | colour::BG_WHITE,
+        colours: None,
+    };
+    pub const MARKER: Pixel = Pixel {
+        char_value: '^',
+        attributes: colour::FG_WHITE,
+        colours: None,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Pixel = Pixel {
+        char_value: render::PIXEL,
+        attributes: colour::FG_RED,
+        colours: None,
+    };
+
+    #[test]
+    fn test_flood_fill() {
+        let mut sprite = Sprite::new(5, 3);
+
+        // A wall down the middle, with a gap only diagonals could cross
+        sprite.draw_line(2, 0, 2, 1, &RED);
+        sprite.set_pixel(3, 2, &RED);
+
+        flood_fill(&mut sprite, 0, 0, &RED);
+
+        for x in 0..5 {
+            for y in 0..3 {
+                let filled = x <= 2 || (x, y) == (3, 2);
+
+                assert_eq!(sprite.get_pixel(x, y) == RED, filled, "at {}, {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut editor = SpriteEditor::new("test.spr", Sprite::new(4, 4), "");
+
+        editor.edit(|sprite| sprite.set_pixel(1, 1, &RED));
+        // Painting over the same colour isn't an undo step
+        editor.edit(|sprite| sprite.set_pixel(1, 1, &RED));
+        editor.cursor_x = 3;
+        editor.resize(-2, 0);
+
+        assert_eq!(editor.undo.len(), 2);
+        assert_eq!(editor.sprite.get_width(), 2);
+        assert_eq!(editor.cursor_x, 1);
+
+        editor.undo();
+        editor.undo();
+        editor.undo();
+
+        assert_eq!(editor.sprite, Sprite::new(4, 4));
+
+        editor.redo();
+
+        assert_eq!(editor.sprite.get_pixel(1, 1), RED);
+        assert_eq!(editor.sprite.get_width(), 4);
+
+        // A new edit drops whatever could still be redone
+        editor.edit(|sprite| sprite.set_pixel(0, 0, &RED));
+
+        assert!(editor.redo.is_empty());
+    }
+
+    #[test]
+    fn test_empty_sprite() {
+        let mut editor = SpriteEditor::new("test.spr", Sprite::new(0, 3), "");
+
+        assert_eq!(editor.sprite.get_width(), 1);
+        assert_eq!(editor.sprite.get_height(), 3);
+
+        editor.move_cursor(2, 5);
+
+        assert_eq!((editor.cursor_x, editor.cursor_y), (0, 2));
+    }
+}
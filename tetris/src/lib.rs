@@ -1,33 +1,33 @@
 // Unfinished Tetris demo based on Javidx9's implementation
 // Plenty of comments to help if I come back to it.
-// Add `[profile.dev] overflow-checks = false` to root Cargo to avoid annoying errors
 use engine::{
-    input::{Key, Keyboard},
-    render::{self, colour, Console, Pixel},
+    input::Key,
+    render::{self, Align, Camera, Colour, Console, Rect, Style},
+    scene::{Scene, SceneStack, Transition},
+    tilemap::Tilemap,
+    time::FixedStep,
+    Config, Context, RunOptions,
 };
 use rand::Rng;
-use std::{thread, time::Duration};
-
-const ASSETS: [Pixel; 9] = [
-    assets::EMPTY,
-    assets::PIECE_1,
-    assets::PIECE_2,
-    assets::PIECE_3,
-    assets::PIECE_4,
-    assets::PIECE_5,
-    assets::PIECE_6,
-    assets::PIECE_7,
-    assets::BORDER,
-];
+
+// The empty board with its border, and the tiles the pieces are drawn with
+const BOARD_MAP: &str = include_str!("../assets/board.map");
 
 const SCREEN_WIDTH: u16 = 80;
 const SCREEN_HEIGHT: u16 = 30;
 
-const DRAW_OFFSET_X: usize = 30;
-const DRAW_OFFSET_Y: usize = 6;
+// Where on screen the board is drawn
+const DRAW_OFFSET_X: i32 = 30;
+const DRAW_OFFSET_Y: i32 = 6;
+
+const TARGET_FPS: f64 = 22.0;
 
-const BOARD_WIDTH: usize = 12;
-const BOARD_HEIGHT: usize = 18;
+// Seconds between each step of the current piece falling
+const DROP_INTERVAL: f64 = 0.9;
+
+// The falling piece is drawn on its own layer, which is moved around with its
+// offset, so the board underneath only has to be drawn when it changes
+const PIECE_LAYER: &str = "piece";
 
 const ZERO_DEGREES: usize = 0;
 const NINETY_DEGREES: usize = 1;
@@ -36,7 +36,7 @@ const TWO_SEVENTY_DEGREES: usize = 3;
 
 const TETROMINOS: [[usize; 16]; 7] = [
     // 0 represents empty space, non-zero represents a block,
-    // where the number represents an ASSETS index
+    // where the number is the block's tile ID in BOARD_MAP
     // Line
     [0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0],
     // T
@@ -54,57 +54,49 @@ const TETROMINOS: [[usize; 16]; 7] = [
 ];
 
 struct Piece {
-    pos_x: usize,
-    pos_y: usize,
+    pos_x: i32,
+    pos_y: i32,
     rotation: usize,
     piece_type: [usize; 16],
 }
 
 impl Piece {
-    fn new() -> Piece {
+    fn new(board: &Tilemap) -> Piece {
         let rand_piece = rand::thread_rng().gen_range(0..6);
 
         Piece {
-            pos_x: BOARD_WIDTH / 2,
+            pos_x: board.get_width() as i32 / 2,
             pos_y: 0,
             rotation: 0,
             piece_type: TETROMINOS[rand_piece],
         }
     }
 
-    fn set_position(&mut self, x: usize, y: usize, board: &[usize; BOARD_WIDTH * BOARD_HEIGHT]) {
+    fn set_position(&mut self, x: i32, y: i32, board: &Tilemap) {
         if self.does_fit(x, y, self.rotation, board) {
             self.pos_x = x;
             self.pos_y = y;
         }
     }
 
-    fn set_rotation(&mut self, rotation: usize, board: &[usize; BOARD_WIDTH * BOARD_HEIGHT]) {
-        if self.does_fit(self.pos_x, self.pos_y, rotation, &board) {
+    fn set_rotation(&mut self, rotation: usize, board: &Tilemap) {
+        if self.does_fit(self.pos_x, self.pos_y, rotation, board) {
             self.rotation += 1;
         }
     }
 
-    fn does_fit(
-        &self,
-        x: usize,
-        y: usize,
-        rotation: usize,
-        board: &[usize; BOARD_WIDTH * BOARD_HEIGHT],
-    ) -> bool {
+    fn does_fit(&self, x: i32, y: i32, rotation: usize, board: &Tilemap) -> bool {
         for px in 0..4 {
             for py in 0..4 {
                 let index = to_4x4_rotated_index(px, py, rotation);
 
-                // Check within bounds
-                if (px + x < BOARD_WIDTH) && (py + y < BOARD_HEIGHT) {
-                    let new_index = to_2d_index(px + x, py + y, BOARD_WIDTH);
+                let (board_x, board_y) = (px as i32 + x, py as i32 + y);
 
-                    // If the current piece index is a block, and the board index on the
-                    // index we want to move that index to is a block, then there's a collision.
-                    if self.piece_type[index] != 0 && board[new_index] != 0 {
-                        return false;
-                    }
+                // If the current piece index is a block, and the board tile we want
+                // to move that index to is solid, then there's a collision. Nothing
+                // outside the board is solid.
+                if self.piece_type[index] != 0 && board.is_solid(board_x, board_y) {
+                    return false;
                 }
             }
         }
@@ -116,186 +108,318 @@ impl Piece {
 
 fn to_4x4_rotated_index(x: usize, y: usize, rotation: usize) -> usize {
     // https://www.youtube.com/watch?v=8OK8_tHeCIA
-    let index = match rotation % 4 {
+    match rotation % 4 {
         ZERO_DEGREES => y * 4 + x,
         NINETY_DEGREES => 12 + y - (x * 4),
         ONE_EIGHTY_DEGREES => 15 - (y * 4) - x,
         TWO_SEVENTY_DEGREES => 3 - y + (x * 4),
         _ => 0,
-    };
-
-    index
+    }
 }
 
-fn to_2d_index(x: usize, y: usize, array_width: usize) -> usize {
-    y * array_width + x
+struct Tetris {
+    board: Tilemap,
+    piece: Piece,
+    board_changed: bool,
+    camera: Camera,
+    // Only fed time while the game is running, so pausing doesn't build up drops
+    drops: FixedStep,
 }
 
-pub fn run() {
-    // ENGINE SETUP //////////
-    let mut console = Console::create(SCREEN_WIDTH, SCREEN_HEIGHT, 16, 16, "TETRIS")
-        .expect("Could not create Console");
-    let mut keyboard = Keyboard::create(vec![
-        Key::ESCAPE,
-        Key::UP,
-        Key::DOWN,
-        Key::LEFT,
-        Key::RIGHT,
-        Key::CHAR_Z,
-    ]);
-
-    // GAME //////////
-    let mut board = [0; BOARD_WIDTH * BOARD_HEIGHT];
-
-    // Create field borders
-    for x in 0..BOARD_WIDTH {
-        for y in 0..BOARD_HEIGHT {
-            let index = to_2d_index(x, y, BOARD_WIDTH);
-
-            // 0 = left border, BOARD_WIDTH - 1 = right border, and BOARD_HEIGHT - 1 = bottom border
-            if x == 0 || x == BOARD_WIDTH - 1 || y == BOARD_HEIGHT - 1 {
-                board[index] = 8; // 8 is the number for the border Pixel in the ASSETS array
-            }
+impl Tetris {
+    fn new() -> Tetris {
+        let board = Tilemap::parse(BOARD_MAP).expect("Invalid board map");
+
+        Tetris {
+            piece: Piece::new(&board),
+            camera: board_camera(&board),
+            board,
+            board_changed: true,
+            drops: FixedStep::new(DROP_INTERVAL),
         }
     }
+}
 
-    let drop_interval = 20;
-    let mut counter = 0;
+impl Scene for Tetris {
+    fn on_enter(&mut self, context: &mut Context) {
+        context.console.add_layer(PIECE_LAYER, 1);
+    }
 
-    let mut piece = Piece::new();
+    fn on_resume(&mut self, context: &mut Context) {
+        // Paused hides the piece and draws over the board
+        if let Some(layer) = context.console.get_layer_mut(PIECE_LAYER) {
+            layer.set_visible(true);
+        }
 
-    loop {
-        // TICK //////////
-        thread::sleep(Duration::from_millis(45));
-        counter += 1;
+        self.board_changed = true;
+    }
 
-        // INPUT //////////
-        keyboard.update_key_states();
+    fn on_exit(&mut self, context: &mut Context) {
+        context.console.remove_layer(PIECE_LAYER);
+    }
+
+    fn on_update(&mut self, context: &mut Context, _delta: f64) -> Transition {
+        let Tetris {
+            board,
+            piece,
+            board_changed,
+            camera,
+            drops,
+        } = self;
+        let keyboard = &context.keyboard;
 
+        // INPUT //////////
         let left = keyboard.get_key_state(Key::LEFT);
         let right = keyboard.get_key_state(Key::RIGHT);
         let down = keyboard.get_key_state(Key::DOWN);
         let key_z = keyboard.get_key_state(Key::CHAR_Z);
 
         if keyboard.get_key_state(Key::ESCAPE).is_pressed() {
-            std::process::exit(0);
+            return Transition::Exit;
+        }
+        if keyboard.get_key_state(Key::CHAR_P).is_pressed() {
+            let board_area = camera.get_viewport();
+
+            return Transition::Push(Box::new(Paused {
+                middle_y: board_area.y + board_area.height / 2,
+            }));
         }
 
         // GAME LOGIC //////////
         if left.is_pressed_or_held() {
-            piece.set_position(piece.pos_x - 1, piece.pos_y, &board);
+            piece.set_position(piece.pos_x - 1, piece.pos_y, board);
         }
         if right.is_pressed_or_held() {
-            piece.set_position(piece.pos_x + 1, piece.pos_y, &board);
+            piece.set_position(piece.pos_x + 1, piece.pos_y, board);
         }
         if down.is_pressed_or_held() {
-            piece.set_position(piece.pos_x, piece.pos_y + 1, &board);
+            piece.set_position(piece.pos_x, piece.pos_y + 1, board);
         }
         if key_z.is_pressed() {
-            piece.set_rotation(piece.rotation + 1, &board);
+            piece.set_rotation(piece.rotation + 1, board);
         }
 
         // Drop the current piece
-        if counter == drop_interval {
-            counter = 0;
-
-            if !piece.does_fit(piece.pos_x, piece.pos_y + 1, piece.rotation, &board) {
+        for _ in 0..drops.advance(context.clock.get_delta()).ticks {
+            if !piece.does_fit(piece.pos_x, piece.pos_y + 1, piece.rotation, board) {
                 // Lock the piece
                 for x in 0..4 {
                     for y in 0..4 {
-                        let board_index =
-                            to_2d_index(x + piece.pos_x, y + piece.pos_y, BOARD_WIDTH);
+                        // Only the piece's blocks are copied, and those always fit on the board
                         let piece_index = to_4x4_rotated_index(x, y, piece.rotation);
 
                         if piece.piece_type[piece_index] != 0 {
-                            board[board_index] = piece.piece_type[piece_index];
+                            board.set_tile(
+                                0,
+                                x as i32 + piece.pos_x,
+                                y as i32 + piece.pos_y,
+                                piece.piece_type[piece_index],
+                            );
                         }
                     }
                 }
 
+                *board_changed = true;
+
                 // Generate new piece
-                piece = Piece::new();
+                *piece = Piece::new(board);
 
                 // Game over
-                if !piece.does_fit(piece.pos_x, piece.pos_y, piece.rotation, &board) {
-                    std::process::exit(0);
+                if !piece.does_fit(piece.pos_x, piece.pos_y, piece.rotation, board) {
+                    return Transition::Replace(Box::new(GameOver));
                 }
             } else {
-                piece.set_position(piece.pos_x, piece.pos_y + 1, &board);
+                piece.set_position(piece.pos_x, piece.pos_y + 1, board);
             }
         }
 
+        Transition::None
+    }
+
+    fn on_draw(&mut self, context: &mut Context) {
+        let Tetris {
+            board,
+            piece,
+            board_changed,
+            camera,
+            ..
+        } = self;
+        let console = &mut context.console;
+
         // RENDER //////////
         // Draw board
-        for x in 0..BOARD_WIDTH {
-            for y in 0..BOARD_HEIGHT {
-                let index = to_2d_index(x, y, BOARD_WIDTH);
-                let board_value = board[index];
-
-                // Each number on the board array represents an index into the
-                // ASSETS array; so if the board indexes value is 8, then we
-                // draw a BORDER Pixel
-                console.draw_pixel(x + DRAW_OFFSET_X, y + DRAW_OFFSET_Y, &ASSETS[board_value]);
-            }
+        if *board_changed {
+            console.fill(0, 0, &render::PIXEL_EMPTY);
+            board.draw(console, camera);
+
+            *board_changed = false;
+        }
+
+        // Draw current piece in the top left of its layer, then move the layer to it
+        console.select_layer(PIECE_LAYER);
+
+        if let Some(layer) = console.get_layer_mut(PIECE_LAYER) {
+            let (screen_x, screen_y) = camera.world_to_screen(piece.pos_x, piece.pos_y);
+
+            layer.clear();
+            layer.set_offset(screen_x, screen_y);
         }
 
-        // Draw current piece
         for x in 0..4 {
             for y in 0..4 {
                 let index = to_4x4_rotated_index(x, y, piece.rotation);
                 let piece_index_value = piece.piece_type[index];
 
-                let (draw_x, draw_y) = (
-                    x + piece.pos_x + DRAW_OFFSET_X,
-                    y + piece.pos_y + DRAW_OFFSET_Y,
-                );
-
                 if piece_index_value != 0 {
-                    console.draw_pixel(draw_x, draw_y, &ASSETS[piece_index_value]);
+                    board
+                        .get_tileset()
+                        .draw_tile(console, piece_index_value, x as i32, y as i32);
                 }
             }
         }
 
-        console
-            .update_screen()
-            .expect("Could not update the screen");
+        console.select_base();
     }
 }
 
-mod assets {
-    use super::{colour, render, Pixel};
+// Board positions are used as world positions, shown in the middle of the screen
+fn board_camera(board: &Tilemap) -> Camera {
+    let bounds = board.get_bounds();
 
-    pub const EMPTY: Pixel = render::PIXEL_EMPTY;
-    pub const BORDER: Pixel = Pixel {
-        char_value: 'O',
-        attributes: colour::FG_DARK_BLUE,
-    };
-    pub const PIECE_1: Pixel = Pixel {
-        char_value: 'A',
-        attributes: colour::FG_DARK_RED,
-    };
-    pub const PIECE_2: Pixel = Pixel {
-        char_value: 'B',
-        attributes: colour::FG_DARK_GREEN,
-    };
-    pub const PIECE_3: Pixel = Pixel {
-        char_value: 'C',
-        attributes: colour::FG_BLUE,
-    };
-    pub const PIECE_4: Pixel = Pixel {
-        char_value: 'D',
-        attributes: colour::FG_MAGENTA,
-    };
-    pub const PIECE_5: Pixel = Pixel {
-        char_value: 'E',
-        attributes: colour::FG_DARK_MAGENTA,
-    };
-    pub const PIECE_6: Pixel = Pixel {
-        char_value: 'F',
-        attributes: colour::FG_YELLOW,
-    };
-    pub const PIECE_7: Pixel = Pixel {
-        char_value: 'G',
-        attributes: colour::FG_DARK_YELLOW,
+    Camera::new(Rect::new(
+        DRAW_OFFSET_X,
+        DRAW_OFFSET_Y,
+        bounds.width,
+        bounds.height,
+    ))
+}
+
+struct Title;
+
+impl Scene for Title {
+    fn on_update(&mut self, context: &mut Context, _delta: f64) -> Transition {
+        let keyboard = &context.keyboard;
+
+        if keyboard.get_key_state(Key::ESCAPE).is_pressed() {
+            Transition::Exit
+        } else if keyboard.get_key_state(Key::SPACE).is_pressed() {
+            Transition::Replace(Box::new(Tetris::new()))
+        } else {
+            Transition::None
+        }
+    }
+
+    fn on_draw(&mut self, context: &mut Context) {
+        let console = &mut context.console;
+
+        console.fill(0, 0, &render::PIXEL_EMPTY);
+        draw_centred_string(console, 12, "T E T R I S", Style::fg(Colour::YELLOW));
+        draw_centred_string(
+            console,
+            15,
+            "Press SPACE to start",
+            Style::fg(Colour::WHITE),
+        );
+        draw_centred_string(
+            console,
+            17,
+            "Arrows move, Z rotates, P pauses, ESC quits",
+            Style::fg(Colour::GREY),
+        );
+    }
+}
+
+// Drawn over the board, which stays visible underneath
+struct Paused {
+    // The screen row through the middle of the board
+    middle_y: i32,
+}
+
+impl Scene for Paused {
+    fn on_enter(&mut self, context: &mut Context) {
+        if let Some(layer) = context.console.get_layer_mut(PIECE_LAYER) {
+            layer.set_visible(false);
+        }
+    }
+
+    fn on_update(&mut self, context: &mut Context, _delta: f64) -> Transition {
+        let keyboard = &context.keyboard;
+
+        if keyboard.get_key_state(Key::ESCAPE).is_pressed() {
+            Transition::Exit
+        } else if keyboard.get_key_state(Key::CHAR_P).is_pressed() {
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
+
+    fn on_draw(&mut self, context: &mut Context) {
+        draw_centred_string(
+            &mut context.console,
+            self.middle_y,
+            " PAUSED ",
+            Style::new(Colour::WHITE, Colour::DARK_RED),
+        );
+    }
+
+    fn is_transparent(&self) -> bool {
+        true
+    }
+}
+
+struct GameOver;
+
+impl Scene for GameOver {
+    fn on_update(&mut self, context: &mut Context, _delta: f64) -> Transition {
+        let keyboard = &context.keyboard;
+
+        if keyboard.get_key_state(Key::ESCAPE).is_pressed() {
+            Transition::Exit
+        } else if keyboard.get_key_state(Key::SPACE).is_pressed() {
+            Transition::Replace(Box::new(Tetris::new()))
+        } else {
+            Transition::None
+        }
+    }
+
+    fn on_draw(&mut self, context: &mut Context) {
+        let console = &mut context.console;
+
+        console.fill(0, 0, &render::PIXEL_EMPTY);
+        draw_centred_string(console, 12, "GAME OVER", Style::fg(Colour::RED));
+        draw_centred_string(
+            console,
+            15,
+            "Press SPACE to play again or ESC to quit",
+            Style::fg(Colour::WHITE),
+        );
+    }
+}
+
+fn draw_centred_string(console: &mut Console, y: i32, string: &str, style: Style) {
+    let line = Rect::new(0, y, console.get_width() as i32, 1);
+
+    console.draw_text(line, string, style, Align::Centre);
+}
+
+pub fn run(options: RunOptions) {
+    let config = Config {
+        keys: vec![
+            Key::ESCAPE,
+            Key::UP,
+            Key::DOWN,
+            Key::LEFT,
+            Key::RIGHT,
+            Key::CHAR_Z,
+            Key::CHAR_P,
+            Key::SPACE,
+        ],
+        target_fps: TARGET_FPS,
+        screenshot_key: Some(Key::F12),
+        ..Config::new(SCREEN_WIDTH, SCREEN_HEIGHT, "TETRIS")
     };
+
+    engine::run(SceneStack::new(Box::new(Title)), options.apply(config))
+        .expect("Could not run TETRIS");
 }